@@ -13,14 +13,43 @@ fn bench_collision_at(c: &mut Criterion) {
     let mut rng = rand::thread_rng();
     let main_raster = random_raster(&mut rng, MAIN_W, MAIN_H, 10);
     let other_raster = random_raster(&mut rng, 100, 20, 2);
-    let pos = (MAIN_W/2, MAIN_H/2);
+    let pos = ((MAIN_W/2) as isize, (MAIN_H/2) as isize);
     c.bench_function(&format!("collision_on_{}x{}", MAIN_W, MAIN_H), |b| b.iter(|| {
         main_raster.collision_check_at(black_box(&other_raster), black_box(pos));
     }));
 }
 
+fn bench_collision_at_word_mask(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    // a mostly empty canvas where most placements are rejected by the word mask
+    let main_raster = random_raster(&mut rng, MAIN_W, MAIN_H, 250);
+    let word_mask = main_raster.word_mask();
+    let other_raster = random_raster(&mut rng, 100, 20, 2);
+    let pos = ((MAIN_W/2) as isize, (MAIN_H/2) as isize);
+    c.bench_function(&format!("sparse_collision_on_{}x{}", MAIN_W, MAIN_H), |b| b.iter(|| {
+        main_raster.collision_check_at(black_box(&other_raster), black_box(pos));
+    }));
+    c.bench_function(&format!("sparse_collision_word_mask_on_{}x{}", MAIN_W, MAIN_H), |b| b.iter(|| {
+        main_raster.collision_check_at_with_word_mask(&word_mask, black_box(&other_raster), black_box(pos));
+    }));
+}
+
+fn bench_collision_at_disjoint(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    // the content of the canvas is on the left half and the sprite is placed on the right half
+    let mut main_raster = BinaryRaster::new(MAIN_W, MAIN_H);
+    main_raster.add_from(&random_raster(&mut rng, MAIN_W/2, MAIN_H, 10), (0, 0));
+    let other_raster = random_raster(&mut rng, 100, 20, 2);
+    let pos = ((MAIN_W*3/4) as isize, (MAIN_H/2) as isize);
+    c.bench_function(&format!("disjoint_collision_on_{}x{}", MAIN_W, MAIN_H), |b| b.iter(|| {
+        main_raster.collision_check_at(black_box(&other_raster), black_box(pos));
+    }));
+}
+
 criterion_group!(
     raster, 
     bench_collision_at, 
+    bench_collision_at_word_mask,
+    bench_collision_at_disjoint,
 );
 criterion_main!(raster);
\ No newline at end of file
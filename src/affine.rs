@@ -0,0 +1,71 @@
+/// A 2D affine transform stored as a 2x3 matrix [[a, b, c], [d, e, f]],
+/// mapping (x, y) to (a*x + b*y + c, d*x + e*y + f)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine2(pub [[f64; 3]; 2]);
+
+impl Affine2 {
+    pub const IDENTITY: Affine2 = Affine2([[1., 0., 0.], [0., 1., 0.]]);
+
+    pub fn translation(tx: f64, ty: f64) -> Self {
+        Affine2([[1., 0., tx], [0., 1., ty]])
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Affine2([[sx, 0., 0.], [0., sy, 0.]])
+    }
+
+    /// A clockwise rotation by angle (in radians) around the origin, with y pointing down
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Affine2([[cos, -sin, 0.], [sin, cos, 0.]])
+    }
+
+    pub fn shear(kx: f64, ky: f64) -> Self {
+        Affine2([[1., kx, 0.], [ky, 1., 0.]])
+    }
+
+    /// The transform applying self and then other
+    pub fn then(&self, other: &Affine2) -> Affine2 {
+        let [[a, b, c], [d, e, f]] = self.0;
+        let [[oa, ob, oc], [od, oe, of]] = other.0;
+        Affine2([
+            [oa * a + ob * d, oa * b + ob * e, oa * c + ob * f + oc],
+            [od * a + oe * d, od * b + oe * e, od * c + oe * f + of],
+        ])
+    }
+
+    /// The transform undoing self, None if self isn't invertible
+    pub fn inverse(&self) -> Option<Affine2> {
+        let [[a, b, c], [d, e, f]] = self.0;
+        let det = a * e - b * d;
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        let (ia, ib, id, ie) = (e / det, -b / det, -d / det, a / det);
+        Some(Affine2([
+            [ia, ib, -(ia * c + ib * f)],
+            [id, ie, -(id * c + ie * f)],
+        ]))
+    }
+
+    /// Applies the transform to the point (x, y)
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [[a, b, c], [d, e, f]] = self.0;
+        (a * x + b * y + c, d * x + e * y + f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Affine2;
+
+    #[test]
+    fn test_compose_inverse() {
+        let transform = Affine2::rotation(0.7).then(&Affine2::scale(2., 3.)).then(&Affine2::translation(5., -1.));
+        let (x, y) = transform.apply(1., 2.);
+        let (back_x, back_y) = transform.inverse().unwrap().apply(x, y);
+        assert!((back_x - 1.).abs() < 1e-9 && (back_y - 2.).abs() < 1e-9);
+        assert_eq!((7., 5.), Affine2::scale(2., 3.).then(&Affine2::translation(5., -1.)).apply(1., 2.));
+        assert_eq!(None, Affine2::scale(0., 1.).inverse());
+    }
+}
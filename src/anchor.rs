@@ -0,0 +1,42 @@
+/// A reference point of a rectangle, used to position rasters relatively to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl Anchor {
+    /// The position of the anchor pixel in a rectangle of the given size
+    pub(crate) fn position_in(self, width: usize, height: usize) -> (isize, isize) {
+        let (right, bottom) = (width as isize - 1, height as isize - 1);
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::TopRight => (right, 0),
+            Anchor::BottomLeft => (0, bottom),
+            Anchor::BottomRight => (right, bottom),
+            Anchor::Center => (width as isize / 2, height as isize / 2),
+        }
+    }
+
+    /// The position of the top left corner of a width x height rectangle whose anchor pixel is at point
+    pub fn top_left(self, point: (usize, usize), width: usize, height: usize) -> (isize, isize) {
+        let (anchor_x, anchor_y) = self.position_in(width, height);
+        (point.0 as isize - anchor_x, point.1 as isize - anchor_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Anchor;
+
+    #[test]
+    fn test_top_left() {
+        assert_eq!((10, 20), Anchor::TopLeft.top_left((10, 20), 5, 4));
+        assert_eq!((6, 17), Anchor::BottomRight.top_left((10, 20), 5, 4));
+        assert_eq!((8, 18), Anchor::Center.top_left((10, 20), 5, 4));
+        assert_eq!((-2, 18), Anchor::Center.top_left((0, 20), 5, 4));
+    }
+}
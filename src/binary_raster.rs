@@ -1,7 +1,8 @@
-use crate::bitline::BitLine;
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
+use crate::{anchor::Anchor, bitline::{BitLine, EMPTY_LINE}, draw::line_points, error::{check_row_lengths, CollisionError, ParseRasterError, RowLengthError}};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct BinaryRaster(Vec<BitLine>);
+pub struct BinaryRaster(pub(crate) Vec<BitLine>);
 
 impl BinaryRaster {
     pub fn new(width: usize, height: usize) -> Self {
@@ -20,27 +21,135 @@ impl BinaryRaster {
         )
     }
 
+    /// Builds a raster from pixels rendered at factor times the resolution, reducing every factor x factor block to a single bit
+    /// A bit is set if the coverage of its block (the mean pixel value, 255 being full coverage) is at least threshold
+    /// Blocks on the right and bottom edges may be smaller, only the pixels inside the source are considered
+    pub fn from_raster_supersampled(pixels: &[u8], width: usize, factor: usize, threshold: f32) -> Self {
+        if width == 0 || factor == 0 {
+            return BinaryRaster(Vec::new());
+        }
+        let height = pixels.len() / width;
+        BinaryRaster::from_fn(width.div_ceil(factor), height.div_ceil(factor), |x, y| {
+            let (x_end, y_end) = (((x + 1) * factor).min(width), ((y + 1) * factor).min(height));
+            let (total, count) = (y * factor..y_end)
+                .flat_map(|src_y| &pixels[(src_y * width + x * factor)..(src_y * width + x_end)])
+                .fold((0, 0), |(total, count), &pixel| (total + pixel as usize, count + 1));
+            total as f32 / (count * u8::MAX as usize) as f32 >= threshold
+        })
+    }
+
+    /// Builds a raster from a slice of rows, failing if the rows don't all have the same length
+    pub fn from_rows(rows: &[&[u8]]) -> Result<Self, RowLengthError> {
+        check_row_lengths(rows.iter().map(|row| row.len()))?;
+        Ok(BinaryRaster(
+            rows.iter().map(|row| BitLine::from_bits(row)).collect(),
+        ))
+    }
+
+    /// Parses a raster from text where each line is a row made of on (1) and off (0) tokens
+    /// `BinaryRaster::from_ascii(&raster.get_display(1), BIT_1, BIT_0)` gives back the raster
+    pub fn from_ascii(art: &str, on: &str, off: &str) -> Result<Self, ParseRasterError> {
+        BinaryRaster::from_ascii_lines(art.split('\n'), on, off)
+    }
+
+    fn from_ascii_lines<'a>(lines: impl Iterator<Item = &'a str>, on: &str, off: &str) -> Result<Self, ParseRasterError> {
+        let mut rows = Vec::new();
+        for (row_i, line) in lines.enumerate() {
+            let mut row = Vec::new();
+            let mut rest = line;
+            while !rest.is_empty() {
+                if let Some(next) = rest.strip_prefix(on) {
+                    row.push(1);
+                    rest = next;
+                } else if let Some(next) = rest.strip_prefix(off) {
+                    row.push(0);
+                    rest = next;
+                } else {
+                    return Err(ParseRasterError::UnknownToken { row: row_i, offset: line.len() - rest.len() });
+                }
+            }
+            rows.push(row);
+        }
+        check_row_lengths(rows.iter().map(|row| row.len()))?;
+        Ok(BinaryRaster(
+            rows.iter().map(|row| BitLine::from_bits(row)).collect(),
+        ))
+    }
+
+    /// Builds a width x height raster where the bit at (x, y) is f(x, y)
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> bool) -> Self {
+        BinaryRaster(
+            (0..height).map(|y| BitLine::from_fn(width, |x| f(x, y))).collect(),
+        )
+    }
+
+    /// The width of the raster in bits (the width of its widest line)
+    pub fn width(&self) -> usize {
+        self.0.iter().map(|bitline| bitline.bits).max().unwrap_or(0)
+    }
+
+    /// The amount of lines in the raster
+    pub fn height(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The value of the bit at (x, y), false if it's outside of the raster
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.0.get(y).is_some_and(|bitline| bitline.get(x))
+    }
+
+    /// Sets the value of the bit at (x, y), does nothing if it's outside of the raster
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        if let Some(bitline) = self.0.get_mut(y) {
+            bitline.set(x, value);
+        }
+    }
+
+    /// The amount of bits with a value of 1 in the raster
+    pub fn count_ones(&self) -> usize {
+        self.0.iter().map(BitLine::count_ones).sum()
+    }
+
     /// The amount of allocated usize to represent the widest bitline
     fn max_chunkwidth(&self) -> usize {
         self.0.iter().map(|bit_line| bit_line.chunk_width()).max().unwrap_or(0)
     }
 
-    fn max_chunkwidth_after_shift(&self, amount: u32) -> usize {
+    fn max_chunkwidth_after_shift(&self, amount: usize) -> usize {
         self.0.iter()
-            .map(|bit_line| BitLine::chunks_to_fit(bit_line.bits+amount as usize))
+            .map(|bit_line| BitLine::chunks_to_fit(bit_line.bits+amount))
             .max().unwrap_or(0)
     }
 
-    fn shifted_right(&self, amount: u32) -> BinaryRaster {
+    /// Shifts every line to the right, adding amount empty columns on the left
+    pub fn shifted_right(&self, amount: usize) -> BinaryRaster {
         if amount == 0 {
             return self.clone();
         }
-        debug_assert!(amount < usize::BITS);
         BinaryRaster (
             self.0.iter().map(|bitline| bitline.shifted_right(amount)).collect(),
         )
     }
 
+    /// Shifts every line to the left, dropping the first amount columns
+    pub fn shifted_left(&self, amount: usize) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().map(|bitline| bitline.shifted_left(amount)).collect(),
+        )
+    }
+
+    /// Shifts the lines up, dropping the first amount lines
+    pub fn shifted_up(&self, amount: usize) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().skip(amount).cloned().collect(),
+        )
+    }
+
+    /// Shifts the lines down, adding amount empty lines on top
+    pub fn shifted_down(&self, amount: usize) -> BinaryRaster {
+        self.padded(0, 0, amount, 0)
+    }
+
     /// Returns true if other fits within self at given pos, false otherwise
     pub fn can_fit(&self, other: &BinaryRaster, pos: (usize, usize)) -> bool {
         let (segment_offset, shift_amount) = BitLine::chunked(pos.0);
@@ -48,52 +157,522 @@ impl BinaryRaster {
         && (pos.1 + other.0.len() < self.0.len())
     }
 
-    fn collision_check(&self, source: &BinaryRaster, segment_offset: usize, line_offset: usize) -> bool {
-        for line_i in 0..source.0.len() {
-            if self.0[line_i + line_offset].collision_check(&source.0[line_i], segment_offset) {
-                return true;
-            }
+    /// Adds entire source to self at the given position if there's no bit collision,
+    /// clipping the parts of source that fall outside of self, including at negative positions
+    /// Returns Ok(()) if the item was added (no collision), and Err(()) otherwise
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked(&mut self, source: &BinaryRaster, pos: (isize, isize)) -> Result<(), ()> {
+        if self.collision_check_at(source, pos) {
+            return Err(())
+        }
+        self.add_from(source, pos);
+        Ok(())
+    }
+
+    /// Adds entire source to self at the given position without checking for collision,
+    /// clipping the parts of source that fall outside of self, including at negative positions
+    pub fn add_from(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(source, pos, self.height());
+        for (line_i, bitline) in lines {
+            self.0[line_i].combine_at(&bitline, segment_offset, |a, b| a | b);
+        }
+    }
+
+    /// Adds entire source to self at the given position without checking for collision, same as add_from,
+    /// returning the amount of bits with a value of 1 of source that were clipped because they fall outside of self
+    pub fn add_from_clipped(&mut self, source: &BinaryRaster, pos: (isize, isize)) -> usize {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(source, pos, self.height());
+        let mut added = 0;
+        for (line_i, bitline) in lines {
+            let line = &mut self.0[line_i];
+            added += bitline.count_ones_range(0, line.bits.saturating_sub(segment_offset * usize::BITS as usize));
+            line.combine_at(&bitline, segment_offset, |a, b| a | b);
+        }
+        source.count_ones() - added
+    }
+
+    /// Adds every raster to self at its position if none of them collide with self or with each other,
+    /// clipping the parts that fall outside of self
+    /// Nothing is added if any item collides, the result of each item tells what it collides with,
+    /// an item that collides is not considered when checking the items after it
+    pub fn add_many_checked(&mut self, items: &[(&BinaryRaster, (isize, isize))]) -> Vec<Result<(), CollisionError>> {
+        let mut staged = BinaryRaster(
+            self.0.iter().map(|bitline| BitLine::new(bitline.bits)).collect(),
+        );
+        let mut results: Vec<Result<(), CollisionError>> = Vec::with_capacity(items.len());
+        for &(sprite, pos) in items {
+            let result = if self.collision_check_at(sprite, pos) {
+                Err(CollisionError::Target)
+            } else if let Some((x, y)) = staged.first_collision_at(sprite, pos) {
+                // the earliest staged item covering the colliding pixel
+                let index = items.iter().zip(results.iter()).position(|(&(item, item_pos), result)| {
+                    let (item_x, item_y) = (x as isize - item_pos.0, y as isize - item_pos.1);
+                    result.is_ok() && item_x >= 0 && item_y >= 0 && item.get(item_x as usize, item_y as usize)
+                });
+                Err(CollisionError::Item(index.expect("a staged item covers the collision")))
+            } else {
+                staged.add_from(sprite, pos);
+                Ok(())
+            };
+            results.push(result);
+        }
+        if results.iter().all(Result::is_ok) {
+            *self |= &staged;
         }
-        false
+        results
+    }
+
+    /// The top left position of source when its anchor is at point
+    fn anchored_pos(source: &BinaryRaster, point: (usize, usize), anchor: Anchor) -> (isize, isize) {
+        anchor.top_left(point, source.width(), source.height())
+    }
+
+    /// Adds entire source to self with its anchor at point without checking for collision,
+    /// clipping the parts of source that fall outside of self
+    pub fn add_from_anchored(&mut self, source: &BinaryRaster, point: (usize, usize), anchor: Anchor) {
+        self.add_from(source, BinaryRaster::anchored_pos(source, point, anchor));
     }
 
-    /// Adds entire source to self at the given position if there's no bit collision, assuming it fits
+    /// Adds entire source to self with its anchor at point if there's no bit collision,
+    /// clipping the parts of source that fall outside of self
     /// Returns Ok(()) if the item was added (no collision), and Err(()) otherwise
-    pub fn add_from_checked(&mut self, source: &BinaryRaster, pos: (usize, usize)) -> Result<(), ()> {
-        let (segment_offset, shift_amount) = BitLine::chunked(pos.0);
-        let source = source.shifted_right(shift_amount);
-        if self.collision_check(&source, segment_offset, pos.1) {
-            return Err(())
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked_anchored(&mut self, source: &BinaryRaster, point: (usize, usize), anchor: Anchor) -> Result<(), ()> {
+        self.add_from_checked(source, BinaryRaster::anchored_pos(source, point, anchor))
+    }
+
+    /// Checks if there's any pixel overlap between other and self with the anchor of other at point
+    pub fn collision_check_anchored(&self, other: &BinaryRaster, point: (usize, usize), anchor: Anchor) -> bool {
+        self.collision_check_at(other, BinaryRaster::anchored_pos(other, point, anchor))
+    }
+
+    /// Removes entire source from self at the given position, undoing a previous add_from(source, pos)
+    /// Bits that were already set in self before adding source are cleared as well,
+    /// which can't happen if source was added with add_from_checked
+    pub fn remove_from(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        self.subtract_at(source, pos);
+    }
+
+    /// The lines of other placed at pos along with their line index in a raster of the given height,
+    /// shifted to be aligned with the chunks of that raster starting at the chunk index returned alongside
+    /// The lines above the top edge or past the height are skipped and the bits left of the left edge are cropped out
+    fn clipped_lines(other: &BinaryRaster, pos: (isize, isize), height: usize) -> (usize, impl Iterator<Item = (usize, BitLine)> + '_) {
+        let segment_offset = BitLine::chunked(pos.0.max(0) as usize).0;
+        let lines = other.0.iter().enumerate()
+            .skip(pos.1.min(0).unsigned_abs())
+            .map(move |(line_i, bitline)| ((line_i as isize + pos.1) as usize, bitline))
+            .take_while(move |&(line_i, _)| line_i < height)
+            .map(move |(line_i, bitline)| (line_i, BinaryRaster::clipped_line(bitline, pos.0)));
+        (segment_offset, lines)
+    }
+
+    /// Same as clipped_lines, skipping the lines with no bit with a value of 1,
+    /// for the operations that leave self untouched where other is 0
+    fn clipped_set_lines(other: &BinaryRaster, pos: (isize, isize), height: usize) -> (usize, impl Iterator<Item = (usize, BitLine)> + '_) {
+        let (segment_offset, lines) = BinaryRaster::clipped_lines(other, pos, height);
+        (segment_offset, lines.filter(|(_, bitline)| bitline.extent().is_some()))
+    }
+
+    /// The bitline placed at x shifted to be aligned with the chunks of a line, starting at the chunk index BitLine::chunked(x.max(0)).0,
+    /// the bits left of the start of the line being cropped out
+    fn clipped_line(bitline: &BitLine, x: isize) -> BitLine {
+        // the line is either cropped on the left or shifted to the right, never both
+        if x < 0 {
+            bitline.shifted_left(x.unsigned_abs())
+        } else {
+            bitline.shifted_right(BitLine::chunked(x as usize).1)
+        }
+    }
+
+    /// Adds entire other to self at the given position, clipping the parts of other that fall outside of self, same as add_from
+    pub fn union_at(&mut self, other: &BinaryRaster, pos: (isize, isize)) {
+        self.add_from(other, pos);
+    }
+
+    /// Adds every raster to self at its position, clipping the parts that fall outside of self
+    /// Each raster is only shifted once per distinct sub-word offset, even if it appears multiple times in the batch
+    pub fn union_many(&mut self, items: &[(&BinaryRaster, (isize, isize))]) {
+        let mut shifted_sprites: HashMap<(*const BinaryRaster, usize), BinaryRaster> = HashMap::new();
+        for &(sprite, pos) in items {
+            // the sprites cropped on the left are different at each position
+            if pos.0 < 0 {
+                self.union_at(sprite, pos);
+                continue;
+            }
+            let (segment_offset, shift_amount) = BitLine::chunked(pos.0 as usize);
+            let shifted = shifted_sprites
+                .entry((sprite as *const BinaryRaster, shift_amount))
+                .or_insert_with(|| sprite.shifted_right(shift_amount));
+            let lines = shifted.0.iter().skip(pos.1.min(0).unsigned_abs());
+            for (bitline, line) in lines.zip(self.0.iter_mut().skip(pos.1.max(0) as usize)) {
+                line.combine_at(bitline, segment_offset, |a, b| a | b);
+            }
         }
-        for line_i in 0..source.0.len() {
-            self.0[line_i + pos.1].add_from(&source.0[line_i], segment_offset);
+    }
+
+    /// ORs other placed at pos into self, same as union_at
+    pub fn or_assign_at(&mut self, other: &BinaryRaster, pos: (isize, isize)) {
+        self.union_at(other, pos);
+    }
+
+    /// ANDs other placed at pos into self, the bits of self outside of the rectangle covered by other are left untouched
+    /// The parts of other that fall outside of self are clipped
+    pub fn and_assign_at(&mut self, other: &BinaryRaster, pos: (isize, isize)) {
+        // the bits covered by other that are 0 in other
+        let holes = other.inverted();
+        let (segment_offset, lines) = BinaryRaster::clipped_lines(&holes, pos, self.height());
+        for (line_i, holes) in lines {
+            self.0[line_i].combine_at(&holes, segment_offset, |a, h| a & !h);
         }
-        Ok(())
     }
 
-    /// Adds entire source to self at the given position without checking for collision, assuming it fits
-    pub fn add_from(&mut self, source: &BinaryRaster, pos: (usize, usize)) {
-        let (segment_offset, shift_amount) = BitLine::chunked(pos.0);
-        let source = source.shifted_right(shift_amount);
-        for line_i in 0..source.0.len() {
-            self.0[line_i + pos.1].add_from(&source.0[line_i], segment_offset);
+    /// XORs other placed at pos into self, clipping the parts of other that fall outside of self
+    pub fn xor_assign_at(&mut self, other: &BinaryRaster, pos: (isize, isize)) {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(other, pos, self.height());
+        for (line_i, bitline) in lines {
+            self.0[line_i].combine_at(&bitline, segment_offset, |a, b| a ^ b);
         }
     }
 
-    /// Checks if there's any pixel overlap between other and self at given pos
-    pub fn collision_check_at(&self, other: &BinaryRaster, pos: (usize, usize)) -> bool {
-        if pos.1 >= self.0.len() {
+    /// Adds source placed at pos to self, only where mask has a value of 1
+    /// mask is in the coordinates of self, bits outside of it are read as 0
+    pub fn apply_masked(&mut self, source: &BinaryRaster, mask: &BinaryRaster, pos: (isize, isize)) {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(source, pos, self.height());
+        for (line_i, bitline) in lines {
+            let mut masked = BitLine::new(self.0[line_i].bits);
+            masked.combine_at(&bitline, segment_offset, |_, b| b);
+            masked.combine_with(mask.0.get(line_i).unwrap_or(&EMPTY_LINE), |b, m| b & m);
+            self.0[line_i].combine_with(&masked, |a, b| a | b);
+        }
+    }
+
+    /// Sets to 0 every bit of self covered by a bit with a value of 1 of other placed at pos,
+    /// clipping the parts of other that fall outside of self
+    pub fn subtract_at(&mut self, other: &BinaryRaster, pos: (isize, isize)) {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(other, pos, self.height());
+        for (line_i, bitline) in lines {
+            self.0[line_i].combine_at(&bitline, segment_offset, |a, b| a & !b);
+        }
+    }
+
+    /// Returns a raster with the size of self holding the bits set in both self and other placed at pos
+    pub fn intersection_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> BinaryRaster {
+        let mut res = BinaryRaster(
+            self.0.iter().map(|bitline| BitLine::new(bitline.bits)).collect(),
+        );
+        let (segment_offset, lines) = BinaryRaster::clipped_lines(other, pos, self.height());
+        for (line_i, bitline) in lines {
+            res.0[line_i].combine_at(&bitline, segment_offset, |_, b| b);
+            res.0[line_i].combine_with(&self.0[line_i], |a, b| a & b);
+        }
+        res
+    }
+
+    /// Checks if there's any pixel overlap between other and self at given pos,
+    /// the parts of other that fall outside of self are ignored, including at negative positions
+    pub fn collision_check_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> bool {
+        self.collision_check_lines(other, pos, |line_i| Cow::Owned(BinaryRaster::clipped_line(&other.0[line_i], pos.0)))
+    }
+
+    /// Same as collision_check_at, except that the overlaps on the bits with a value of 1 of ignore don't count
+    /// ignore is in the coordinates of self, the bits outside of it are read as 0
+    pub fn collision_check_at_masked(&self, other: &BinaryRaster, pos: (isize, isize), ignore: &BinaryRaster) -> bool {
+        let (segment_offset, mut lines) = BinaryRaster::clipped_lines(other, pos, self.height());
+        lines.any(|(line_i, bitline)| {
+            let (line, ignored) = (&self.0[line_i], ignore.0.get(line_i).unwrap_or(&EMPTY_LINE));
+            (0..bitline.chunk_width()).any(|i| {
+                let chunk_i = i + segment_offset;
+                line.chunk(chunk_i) & !ignored.chunk(chunk_i) & bitline.chunk(i) != 0
+            })
+        })
+    }
+
+    /// Checks if there's any pixel overlap between other and self at given pos,
+    /// where aligned(line_i) is the line line_i of other aligned with the chunks of self by clipped_line
+    pub(crate) fn collision_check_lines<'a>(&self, other: &BinaryRaster, pos: (isize, isize), aligned: impl Fn(usize) -> Cow<'a, BitLine>) -> bool {
+        let segment_offset = BitLine::chunked(pos.0.max(0) as usize).0;
+        // only the lines of other placed within the height of self are checked
+        let last = (self.height() as isize - pos.1).clamp(0, other.height() as isize) as usize;
+        other.0.iter().enumerate().take(last).skip(pos.1.min(0).unsigned_abs()).any(|(line_i, bitline)| {
+            let line = &self.0[(line_i as isize + pos.1) as usize];
+            // lines whose extents don't overlap are skipped before aligning the line of other
+            let (Some((start, end)), Some((other_start, other_end))) = (line.extent(), bitline.extent()) else {
+                return false;
+            };
+            if pos.0 + (other_end as isize) < start as isize || pos.0 + (other_start as isize) > end as isize {
+                return false;
+            }
+            line.collision_check(&aligned(line_i), segment_offset)
+        })
+    }
+
+    /// The position in self of the first pixel overlapping between other and self at given pos, in reading order,
+    /// None if there's no collision
+    pub fn first_collision_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> Option<(usize, usize)> {
+        let (segment_offset, mut lines) = BinaryRaster::clipped_lines(other, pos, self.height());
+        lines.find_map(|(line_i, bitline)| Some((self.0[line_i].first_collision(&bitline, segment_offset)?, line_i)))
+    }
+
+    /// The pixels overlapping between other and self at given pos in a raster with the size of self,
+    /// None if there's no collision
+    pub fn collision_mask_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> Option<BinaryRaster> {
+        // the lines above the first collision are empty in the mask
+        let first_line = self.first_collision_at(other, pos)?.1;
+        let mut res = BinaryRaster(
+            self.0.iter().map(|bitline| BitLine::new(bitline.bits)).collect(),
+        );
+        let (segment_offset, lines) = BinaryRaster::clipped_lines(other, pos, self.height());
+        for (line_i, bitline) in lines.skip_while(|&(line_i, _)| line_i < first_line) {
+            res.0[line_i].combine_at(&bitline, segment_offset, |_, b| b);
+            res.0[line_i].combine_with(&self.0[line_i], |a, b| a & b);
+        }
+        Some(res)
+    }
+
+    /// The smallest axis-aligned translation (dx, 0) or (0, dy) that makes other placed at pos stop colliding with self,
+    /// (0, 0) if there's no collision, ties are broken in the order left, right, up, down
+    /// Returns None if other has to leave the bounds of self entirely to stop colliding
+    pub fn resolve_overlap_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> Option<(isize, isize)> {
+        if !self.collision_check_at(other, pos) {
+            return Some((0, 0));
+        }
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        let (other_width, other_height) = (other.width() as isize, other.height() as isize);
+        let candidates = (1..(width + other_width).max(height + other_height))
+            .flat_map(|d| [(-d, 0), (d, 0), (0, -d), (0, d)])
+            .map(move |(dx, dy)| (pos.0 + dx, pos.1 + dy))
+            .filter(move |&(x, y)| x > -other_width && x < width && y > -other_height && y < height);
+        // other is only shifted once per sub-word offset, every vertical translation sharing the one of pos.0
+        self.collision_checks(other, candidates)
+            .find(|&(_, collides)| !collides)
+            .map(|((x, y), _)| (x - pos.0, y - pos.1))
+    }
+
+    /// Moves other from from_pos to to_pos one pixel at a time along a Bresenham line (ends included),
+    /// returning the first position where it collides with self, None if the whole path is free
+    pub fn collision_sweep(&self, other: &BinaryRaster, from_pos: (isize, isize), to_pos: (isize, isize)) -> Option<(isize, isize)> {
+        line_points(from_pos, to_pos).find(|&pos| self.collision_check_at(other, pos))
+    }
+
+    /// The first bit with a value of 1 along the Bresenham line from `from` to `to` (ends included), None if the line is clear
+    /// The points of the line on the same row are checked together with word operations, so mostly horizontal rays are cheap
+    pub fn raycast(&self, from: (usize, usize), to: (usize, usize)) -> Option<(usize, usize)> {
+        let leftward = to.0 < from.0;
+        let mut points = line_points((from.0 as isize, from.1 as isize), (to.0 as isize, to.1 as isize)).peekable();
+        while let Some((x, y)) = points.next() {
+            // the last point of the row of the line starting at (x, y)
+            let mut end = x;
+            while let Some((next_x, _)) = points.next_if(|&(_, next_y)| next_y == y) {
+                end = next_x;
+            }
+            let Some(bitline) = self.0.get(y as usize) else {
+                continue;
+            };
+            let (low, high) = (x.min(end) as usize, x.max(end) as usize);
+            let hit = if leftward {
+                bitline.prev_one(high).filter(|&hit| hit >= low)
+            } else {
+                bitline.next_one(low).filter(|&hit| hit <= high)
+            };
+            if let Some(hit) = hit {
+                return Some((hit, y as usize));
+            }
+        }
+        None
+    }
+
+    /// Checks if there's any pixel overlap between a placed at pos_a and b placed at pos_b, both in world coordinates
+    pub fn collide(a: &BinaryRaster, pos_a: (isize, isize), b: &BinaryRaster, pos_b: (isize, isize)) -> bool {
+        a.collision_check_at(b, (pos_b.0 - pos_a.0, pos_b.1 - pos_a.1))
+    }
+
+    /// Checks if there's any pixel overlap between other and self at each position, same as collision_check_at
+    /// other is only shifted once per distinct sub-word offset, even if it appears at many positions
+    pub fn collision_check_many(&self, other: &BinaryRaster, positions: &[(isize, isize)]) -> Vec<bool> {
+        self.collision_checks(other, positions.iter().copied()).map(|(_, collides)| collides).collect()
+    }
+
+    /// Lazily checks collision_check_at for each position, yielding the positions along with the results
+    /// other is only shifted once per distinct sub-word offset, even if it appears at many positions
+    pub(crate) fn collision_checks<'a>(
+        &'a self, other: &'a BinaryRaster, positions: impl Iterator<Item = (isize, isize)> + 'a
+    ) -> impl Iterator<Item = ((isize, isize), bool)> + 'a {
+        let mut shifted_sprites: HashMap<isize, Vec<BitLine>> = HashMap::new();
+        positions.map(move |pos| {
+            // positions with the same offset inside of a chunk only differ by whole chunks
+            let aligned_x = if pos.0 < 0 { pos.0 } else { pos.0 % usize::BITS as isize };
+            let lines = shifted_sprites.entry(aligned_x).or_insert_with(|| {
+                BinaryRaster::clipped_lines(other, (aligned_x, 0), other.height()).1.map(|(_, bitline)| bitline).collect()
+            });
+            let segment_offset = BitLine::chunked(pos.0.max(0) as usize).0;
+            let collides = lines.iter().enumerate().any(|(line_i, bitline)| {
+                let line_i = line_i as isize + pos.1;
+                line_i >= 0 && (line_i as usize) < self.height()
+                    && self.0[line_i as usize].collision_check(bitline, segment_offset)
+            });
+            (pos, collides)
+        })
+    }
+
+    /// Checks if there's any pixel overlap between other and self at given pos, treating self as a torus:
+    /// the parts of other that go past an edge of self come back from the opposite edge
+    pub fn collision_check_wrapping(&self, other: &BinaryRaster, pos: (usize, usize)) -> bool {
+        let (width, height) = (self.width(), self.height());
+        if height == 0 {
             return false;
         }
-        let (segment_offset, shift_amount) = BitLine::chunked(pos.0);
-        let other = other.shifted_right(shift_amount);
-        let other_height = (other.0.len()+pos.1).min(self.0.len())-pos.1;
-        for line_i in 0..other_height {
-            if self.0[line_i + pos.1].collision_check(&other.0[line_i], segment_offset) {
-                return true;
+        other.0.iter().enumerate().any(|(line_i, bitline)| {
+            self.0[(line_i + pos.1) % height].collision_check(&bitline.wrapped(width, pos.0), 0)
+        })
+    }
+
+    /// The smallest rectangle (x0, y0, x1, y1) containing every bit with a value of 1, bounds included
+    /// Returns None if the raster is empty
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let y0 = self.0.iter().position(|bitline| bitline.start().is_some())?;
+        let y1 = self.0.iter().rposition(|bitline| bitline.end().is_some())?;
+        let lines = &self.0[y0..=y1];
+        let x0 = lines.iter().filter_map(|bitline| bitline.start()).min()?;
+        let x1 = lines.iter().filter_map(|bitline| bitline.end()).max()?;
+        Some((x0, y0, x1, y1))
+    }
+
+    /// Returns a new raster trimmed to the bounding box of the bits with a value of 1,
+    /// along with the position of the trimmed raster in self
+    /// An empty raster is trimmed to a 0x0 raster at (0, 0)
+    pub fn crop_to_content(&self) -> (BinaryRaster, (usize, usize)) {
+        let Some((x0, y0, x1, y1)) = self.bounding_box() else {
+            return (BinaryRaster(Vec::new()), (0, 0));
+        };
+        (self.crop(x0, y0, x1 - x0 + 1, y1 - y0 + 1), (x0, y0))
+    }
+
+    /// Extracts the w x h rectangle with its top left corner at (x, y) into a new raster
+    /// The rectangle is clipped to the bounds of self
+    pub fn crop(&self, x: usize, y: usize, w: usize, h: usize) -> BinaryRaster {
+        let w = w.min(self.width().saturating_sub(x));
+        let h = h.min(self.height().saturating_sub(y));
+        BinaryRaster(
+            self.0.iter().skip(y).take(h).map(|bitline| bitline.sub_line(x, w)).collect(),
+        )
+    }
+
+    /// Returns a copy of the raster grown with bits with a value of 0 on each side
+    pub fn padded(&self, left: usize, right: usize, top: usize, bottom: usize) -> BinaryRaster {
+        let mut res = self.clone();
+        res.pad(left, right, top, bottom);
+        res
+    }
+
+    /// Grows the raster with bits with a value of 0 on each side
+    pub fn pad(&mut self, left: usize, right: usize, top: usize, bottom: usize) {
+        let width = left + self.width() + right;
+        if left != 0 || right != 0 {
+            for bitline in self.0.iter_mut() {
+                *bitline = bitline.padded(left, width - left - bitline.bits);
             }
         }
-        false
+        self.0.splice(0..0, (0..top).map(|_| BitLine::new(width)));
+        self.0.extend((0..bottom).map(|_| BitLine::new(width)));
+    }
+
+    /// Sets every bit of the raster to 0
+    pub fn clear(&mut self) {
+        self.0.iter_mut().for_each(BitLine::clear);
+    }
+
+    /// Sets every bit of the raster to 1
+    pub fn fill(&mut self) {
+        self.0.iter_mut().for_each(BitLine::fill);
+    }
+
+    /// Flips every bit of the raster
+    pub fn invert(&mut self) {
+        self.0.iter_mut().for_each(BitLine::invert);
+    }
+
+    /// Returns a copy of the raster with every bit flipped
+    pub fn inverted(&self) -> BinaryRaster {
+        let mut res = self.clone();
+        res.invert();
+        res
+    }
+
+    /// Returns a new raster where every bit is f applied to the bit of self at the same position
+    pub fn map(&self, f: impl Fn(bool) -> bool) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().map(|bitline| bitline.map(&f)).collect(),
+        )
+    }
+
+    /// Returns a new raster with the size of self where every bit is f applied to the bits of self and other at the same position
+    /// Bits outside of other are read as 0
+    pub fn zip_with(&self, other: &BinaryRaster, f: impl Fn(bool, bool) -> bool) -> BinaryRaster {
+        let empty = BitLine::new(0);
+        BinaryRaster(
+            self.0.iter().enumerate()
+                .map(|(line_i, bitline)| bitline.zip_with(other.0.get(line_i).unwrap_or(&empty), &f))
+                .collect(),
+        )
+    }
+
+    /// Returns a new raster with the bits set in self but not in other
+    /// Rasters of different sizes are clipped to the size of self, bits outside of other are read as 0
+    pub fn difference(&self, other: &BinaryRaster) -> BinaryRaster {
+        self.zip_with(other, |a, b| a && !b)
+    }
+
+    /// Returns a new raster with the bits set in either self or other but not both
+    /// Rasters of different sizes are clipped to the size of self, bits outside of other are read as 0
+    pub fn symmetric_difference(&self, other: &BinaryRaster) -> BinaryRaster {
+        self.zip_with(other, |a, b| a != b)
+    }
+
+    /// Sets every bit of the w x h rectangle with its top left corner at (x, y) to 1, clipped to the raster
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        for bitline in self.0.iter_mut().skip(y).take(h) {
+            bitline.fill_range(x, x.saturating_add(w));
+        }
+    }
+
+    /// Sets every bit of the w x h rectangle with its top left corner at (x, y) to 0, clipped to the raster
+    pub fn clear_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        for bitline in self.0.iter_mut().skip(y).take(h) {
+            bitline.clear_range(x, x.saturating_add(w));
+        }
+    }
+
+    /// Flips every bit of the w x h rectangle with its top left corner at (x, y), clipped to the raster
+    pub fn toggle_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        for bitline in self.0.iter_mut().skip(y).take(h) {
+            bitline.toggle_range(x, x.saturating_add(w));
+        }
+    }
+
+    /// True if every bit of the w x h rectangle with its top left corner at (x, y) has a value of 0, bits outside of the raster being read as 0
+    /// Checked with word masks on each line, without building a rectangle raster
+    pub fn is_rect_empty(&self, x: usize, y: usize, w: usize, h: usize) -> bool {
+        self.0.iter().skip(y).take(h).all(|bitline| bitline.is_range_empty(x, x.saturating_add(w)))
+    }
+
+    /// Returns a copy of the raster resized to width x height, cropping or padding with bits with a value of 0 as needed
+    /// The anchor of the content stays at the same place relatively to the anchor of the raster
+    pub fn resized(&self, width: usize, height: usize, anchor: Anchor) -> BinaryRaster {
+        let (old_x, old_y) = anchor.position_in(self.width(), self.height());
+        let (new_x, new_y) = anchor.position_in(width, height);
+        let (dx, dy) = (new_x - old_x, new_y - old_y);
+        let mut res = self.crop((-dx).max(0) as usize, (-dy).max(0) as usize, width, height);
+        let (left, top) = (dx.max(0) as usize, dy.max(0) as usize);
+        let right = width.saturating_sub(left + res.width());
+        let bottom = height.saturating_sub(top + res.height());
+        res.pad(left, right, top, bottom);
+        res
+    }
+
+    /// Counts the pixels overlapping between other and self at given pos
+    pub fn overlap_count_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> usize {
+        let (segment_offset, lines) = BinaryRaster::clipped_set_lines(other, pos, self.height());
+        lines.map(|(line_i, bitline)| self.0[line_i].overlap_count(&bitline, segment_offset)).sum()
     }
 
     /// Gets a String display of the raster at the desired resolution, with "■" for 1 and " " for 0
@@ -110,16 +689,136 @@ impl BinaryRaster {
     }
 }
 
+impl TryFrom<Vec<Vec<bool>>> for BinaryRaster {
+    type Error = RowLengthError;
+
+    fn try_from(rows: Vec<Vec<bool>>) -> Result<Self, Self::Error> {
+        check_row_lengths(rows.iter().map(|row| row.len()))?;
+        Ok(BinaryRaster(
+            rows.iter().map(|row| BitLine::from_fn(row.len(), |x| row[x])).collect(),
+        ))
+    }
+}
+
+/// Parses a raster drawn with '#' for 1 and '.' for 0, ignoring blank lines and whitespace around each line
+impl FromStr for BinaryRaster {
+    type Err = ParseRasterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        BinaryRaster::from_ascii_lines(lines, "#", ".")
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
     use rand::{rngs::ThreadRng, Rng};
     use super::BinaryRaster;
+    use crate::{Anchor, CollisionError, ParseRasterError, RowLengthError, TranslateMode, BIT_0, BIT_1};
     
     fn random_raster(rng: &mut ThreadRng, width: usize, height: usize, zero_to_one_ratio: u8) -> BinaryRaster {
         let pixels = (0..width*height).map(|_| 1-rng.gen_range(0..=zero_to_one_ratio).min(1)).collect::<Vec<_>>();
         BinaryRaster::from_raster(&pixels, width)
     }
     
+    #[test]
+    fn test_from_raster_supersampled() {
+        let pixels = [
+            255, 255, 0, 0, 128,
+            255, 0, 0, 64, 128,
+            0, 0, 255, 255, 0,
+        ];
+        let raster: BinaryRaster = "
+            #..
+            .#.
+        ".parse().unwrap();
+        assert_eq!(raster, BinaryRaster::from_raster_supersampled(&pixels, 5, 2, 0.6));
+        let raster: BinaryRaster = "
+            ###
+            .#.
+        ".parse().unwrap();
+        assert_eq!(raster, BinaryRaster::from_raster_supersampled(&pixels, 5, 2, 0.05));
+    }
+
+    #[test]
+    fn test_from_fn() {
+        let checkerboard = BinaryRaster::from_raster(&[
+            1, 0, 1,
+            0, 1, 0,
+        ], 3);
+        assert_eq!(checkerboard, BinaryRaster::from_fn(3, 2, |x, y| (x + y) % 2 == 0));
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let raster = BinaryRaster::from_raster(&[
+            1, 0, 1,
+            0, 1, 0,
+        ], 3);
+        assert_eq!(Ok(raster.clone()), BinaryRaster::from_rows(&[&[1, 0, 1], &[0, 1, 0]]));
+        assert_eq!(Ok(raster), BinaryRaster::try_from(vec![vec![true, false, true], vec![false, true, false]]));
+        assert_eq!(
+            Err(RowLengthError { row: 1, expected: 3, found: 2 }),
+            BinaryRaster::from_rows(&[&[1, 0, 1], &[0, 1]])
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        let raster = BinaryRaster::from_raster(&[
+            0, 1, 0,
+            1, 1, 1,
+        ], 3);
+        assert_eq!(Ok(raster.clone()), "
+            .#.
+            ###
+        ".parse());
+        assert_eq!(Ok(raster.clone()), BinaryRaster::from_ascii(&raster.get_display(1), BIT_1, BIT_0));
+        assert_eq!(Err(ParseRasterError::UnknownToken { row: 1, offset: 2 }), ".#.\n##?".parse::<BinaryRaster>());
+        assert_eq!(
+            Err(ParseRasterError::RowLength(RowLengthError { row: 1, expected: 3, found: 2 })),
+            ".#.\n##".parse::<BinaryRaster>()
+        );
+    }
+
+    #[test]
+    fn test_ascii_round_trip() {
+        // these rasters grow past the chunks allocated for their lines
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 30, 5, 2);
+        let grown = [
+            raster.padded(3, 100, 1, 2),
+            raster.concat_horizontal(&BinaryRaster::new(90, 5)),
+            raster.translated(70, 2, TranslateMode::Grow),
+            raster.shifted_right(40),
+        ];
+        for raster in grown {
+            assert_eq!(Ok(raster.clone()), BinaryRaster::from_ascii(&raster.get_display(1), BIT_1, BIT_0));
+        }
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut raster = BinaryRaster::new(3, 2);
+        raster.set(2, 1, true);
+        raster.set(3, 1, true);
+        raster.set(0, 2, true);
+        assert!(raster.get(2, 1));
+        assert!(!raster.get(1, 1));
+        assert!(!raster.get(0, 2));
+        assert_eq!(1, raster.count_ones());
+    }
+
+    #[test]
+    fn test_count_ones() {
+        let raster: BinaryRaster = "
+            #..#
+            .##.
+        ".parse().unwrap();
+        assert_eq!(4, raster.count_ones());
+    }
+
     #[test]
     fn test_right_shift() {
         let pixels = vec![
@@ -139,6 +838,33 @@ mod tests {
             0, 0, 1, 1, 1, 0,
         ];
         assert_eq!(BinaryRaster::from_raster(&shifted_pixels, 6), shifted_raster);
+        let shifted_raster = raster.shifted_right(100);
+        assert_eq!(raster.padded(100, 0, 0, 0), shifted_raster);
+        assert_eq!(raster, shifted_raster.shifted_left(100));
+    }
+
+    #[test]
+    fn test_other_shifts() {
+        let raster: BinaryRaster = "
+            #.#.
+            .##.
+        ".parse().unwrap();
+        let left: BinaryRaster = "
+            .#.
+            ##.
+        ".parse().unwrap();
+        let up: BinaryRaster = "
+            .##.
+        ".parse().unwrap();
+        let down: BinaryRaster = "
+            ....
+            #.#.
+            .##.
+        ".parse().unwrap();
+        assert_eq!(left, raster.shifted_left(1));
+        assert_eq!(up, raster.shifted_up(1));
+        assert_eq!(down, raster.shifted_down(1));
+        assert_eq!(0, raster.shifted_up(3).height());
     }
 
     #[test]
@@ -185,6 +911,240 @@ mod tests {
         assert_eq!(Err(()), res);
     }
 
+    #[test]
+    fn test_union_at() {
+        let mut raster: BinaryRaster = "
+            #....
+            .....
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        raster.union_at(&sprite, (3, 1));
+        let truth: BinaryRaster = "
+            #....
+            ...##
+            ...#.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        raster.union_at(&sprite, (0, 2));
+        let truth: BinaryRaster = "
+            #....
+            ...##
+            ####.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_add_many_checked() {
+        let mut raster: BinaryRaster = "
+            #....
+            .....
+            .....
+        ".parse().unwrap();
+        let block: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        let dot: BinaryRaster = "#".parse().unwrap();
+        let original = raster.clone();
+        let results = raster.add_many_checked(&[(&block, (1, 0)), (&dot, (0, 0)), (&dot, (2, 1)), (&block, (3, 1))]);
+        assert_eq!(vec![Ok(()), Err(CollisionError::Target), Err(CollisionError::Item(0)), Ok(())], results);
+        assert_eq!(original, raster);
+        let results = raster.add_many_checked(&[(&block, (1, 0)), (&dot, (0, 2)), (&block, (3, 1)), (&dot, (-1, 0))]);
+        assert!(results.iter().all(Result::is_ok));
+        let truth: BinaryRaster = "
+            ###..
+            .####
+            #..##
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_union_many() {
+        let mut rng = rand::thread_rng();
+        let sprite_a = random_raster(&mut rng, 70, 5, 3);
+        let sprite_b = random_raster(&mut rng, 10, 3, 1);
+        let items = [
+            (&sprite_a, (3, 0)),
+            (&sprite_b, (67, 4)),
+            (&sprite_a, (131, 10)),
+            (&sprite_b, (195, 18)),
+            (&sprite_a, (-30, -2)),
+            (&sprite_b, (-3, 7)),
+            (&sprite_a, (70, -4)),
+        ];
+        let mut batched = random_raster(&mut rng, 200, 20, 10);
+        let mut one_by_one = batched.clone();
+        batched.union_many(&items);
+        for (sprite, pos) in items {
+            one_by_one.union_at(sprite, pos);
+        }
+        assert_eq!(one_by_one, batched);
+    }
+
+    #[test]
+    fn test_intersection_at() {
+        let raster: BinaryRaster = "
+            #..##
+            .#.##
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        let truth: BinaryRaster = "
+            .....
+            ...##
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster.intersection_at(&sprite, (3, 1)));
+    }
+
+    #[test]
+    fn test_assign_at() {
+        let raster: BinaryRaster = "
+            #..##
+            .####
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        let mut and = raster.clone();
+        and.and_assign_at(&sprite, (2, 0));
+        let truth: BinaryRaster = "
+            #..##
+            .##.#
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, and);
+        let mut xor = raster.clone();
+        xor.xor_assign_at(&sprite, (2, 1));
+        let truth: BinaryRaster = "
+            #..##
+            .#...
+            ..#.#
+        ".parse().unwrap();
+        assert_eq!(truth, xor);
+        let mut or = raster.clone();
+        or.or_assign_at(&sprite, (0, 1));
+        let truth: BinaryRaster = "
+            #..##
+            #####
+            #.#..
+        ".parse().unwrap();
+        assert_eq!(truth, or);
+    }
+
+    #[test]
+    fn test_apply_masked() {
+        let mut raster: BinaryRaster = "
+            #....
+            .....
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            ###
+        ".parse().unwrap();
+        let mask: BinaryRaster = "
+            ##.##
+            ###..
+        ".parse().unwrap();
+        raster.apply_masked(&sprite, &mask, (1, 0));
+        let truth: BinaryRaster = "
+            ##.#.
+            .##..
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_subtract_at() {
+        let mut raster: BinaryRaster = "
+            #..##
+            .####
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        raster.subtract_at(&sprite, (2, 0));
+        let truth: BinaryRaster = "
+            #....
+            .#.#.
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_remove_from() {
+        let mut rng = rand::thread_rng();
+        let mut main_raster = random_raster(&mut rng, 200, 20, 10);
+        let original = main_raster.clone();
+        let sprite = random_raster(&mut rng, 70, 5, 3);
+        main_raster.add_from(&sprite, (100, 10));
+        main_raster.remove_from(&sprite, (100, 10));
+        assert_eq!(original.difference(&sprite.padded(100, 30, 10, 5)), main_raster);
+        let mut empty = BinaryRaster::new(200, 20);
+        empty.add_from(&sprite, (67, 3));
+        empty.remove_from(&sprite, (67, 3));
+        assert_eq!(BinaryRaster::new(200, 20), empty);
+        // remove_from inverts add_from at negative positions too
+        for pos in [(-30, 2), (5, -3), (-65, -4), (150, 17)] {
+            let mut empty = BinaryRaster::new(200, 20);
+            empty.add_from(&sprite, pos);
+            assert!(pos.0 >= 0 || empty.count_ones() < sprite.count_ones());
+            empty.remove_from(&sprite, pos);
+            assert_eq!(BinaryRaster::new(200, 20), empty);
+        }
+    }
+
+    #[test]
+    fn test_placement_ops_clipping() {
+        type PlacedOp = fn(&mut BinaryRaster, &BinaryRaster, (isize, isize));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 150, 20, 2);
+        let sprite = random_raster(&mut rng, 70, 6, 1);
+        let mask = random_raster(&mut rng, 150, 20, 1);
+        for _ in 0..100 {
+            let pos = (rng.gen_range(-75..155), rng.gen_range(-8..22));
+            // the part of sprite inside of raster, placed in a raster with the size of raster
+            let mut placed = BinaryRaster::new(150, 20);
+            placed.add_from(&sprite, pos);
+            let mut footprint = BinaryRaster::new(150, 20);
+            footprint.add_from(&BinaryRaster::from_fn(70, 6, |_, _| true), pos);
+            assert_eq!(raster.zip_with(&placed, |a, b| a && b), raster.intersection_at(&sprite, pos));
+            assert_eq!(raster.zip_with(&placed, |a, b| a && b).count_ones(), raster.overlap_count_at(&sprite, pos));
+            let check = |op: PlacedOp, truth: fn(bool, bool) -> bool| {
+                let mut res = raster.clone();
+                op(&mut res, &sprite, pos);
+                assert_eq!(raster.zip_with(&placed, truth), res);
+            };
+            check(BinaryRaster::union_at, |a, b| a || b);
+            check(BinaryRaster::xor_assign_at, |a, b| a != b);
+            check(BinaryRaster::subtract_at, |a, b| a && !b);
+            check(BinaryRaster::remove_from, |a, b| a && !b);
+            let mut anded = raster.clone();
+            anded.and_assign_at(&sprite, pos);
+            let outside = raster.difference(&footprint);
+            assert_eq!(raster.zip_with(&placed, |a, b| a && b).zip_with(&outside, |a, b| a || b), anded);
+            let mut masked = raster.clone();
+            masked.apply_masked(&sprite, &mask, pos);
+            assert_eq!(raster.zip_with(&placed.zip_with(&mask, |b, m| b && m), |a, b| a || b), masked);
+        }
+    }
+
     #[test]
     fn test_collision_at() {
         let raster_a = BinaryRaster::from_raster(&vec![
@@ -205,6 +1165,313 @@ mod tests {
         assert!(raster_a.collision_check_at(&raster_b, (2, 4)));
     }
 
+    #[test]
+    fn test_signed_clipping() {
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 150, 12, 4);
+        let sprite = random_raster(&mut rng, 80, 6, 2);
+        for pos in [(-70, -4), (-3, 2), (100, -5), (120, 9), (-80, 0), (0, -6), (150, 3), (-1, 11)] {
+            let covered = |x: usize, y: usize| {
+                let (sprite_x, sprite_y) = (x as isize - pos.0, y as isize - pos.1);
+                sprite_x >= 0 && sprite_y >= 0 && sprite.get(sprite_x as usize, sprite_y as usize)
+            };
+            let collides = (0..12).any(|y| (0..150).any(|x| raster.get(x, y) && covered(x, y)));
+            assert_eq!(collides, raster.collision_check_at(&sprite, pos));
+            let mut added = raster.clone();
+            added.add_from(&sprite, pos);
+            assert_eq!(BinaryRaster::from_fn(150, 12, |x, y| raster.get(x, y) || covered(x, y)), added);
+            let mut checked = raster.clone();
+            assert_eq!(collides, checked.add_from_checked(&sprite, pos).is_err());
+            assert_eq!(if collides { &raster } else { &added }, &checked);
+        }
+    }
+
+    #[test]
+    fn test_collision_mask_at() {
+        let raster: BinaryRaster = "
+            #..##
+            .#.##
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        let truth: BinaryRaster = "
+            #....
+            .#...
+            .....
+        ".parse().unwrap();
+        assert_eq!(Some(truth), raster.collision_mask_at(&sprite, (-1, 0)));
+        assert_eq!(None, raster.collision_mask_at(&sprite, (0, 2)));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 200, 12, 5);
+        let sprite = random_raster(&mut rng, 90, 6, 3);
+        assert_eq!(Some(raster.intersection_at(&sprite, (70, 4))), raster.collision_mask_at(&sprite, (70, 4)));
+    }
+
+    #[test]
+    fn test_resolve_overlap_at() {
+        let raster: BinaryRaster = "
+            ......
+            ..##..
+            ..##..
+            ......
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert_eq!(Some((0, 0)), raster.resolve_overlap_at(&sprite, (0, 0)));
+        assert_eq!(Some((-1, 0)), raster.resolve_overlap_at(&sprite, (1, 1)));
+        assert_eq!(Some((-2, 0)), raster.resolve_overlap_at(&sprite, (2, 1)));
+        assert_eq!(Some((0, -1)), raster.resolve_overlap_at(&sprite, (2, 0)));
+        let mut full = BinaryRaster::new(4, 3);
+        full.fill();
+        assert_eq!(None, full.resolve_overlap_at(&sprite, (1, 1)));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 100, 30, 2);
+        let sprite = random_raster(&mut rng, 8, 5, 1);
+        // the first free translation when trying every one by increasing distance, in the bounds of the raster
+        let truth = (0..130).flat_map(|d| [(-d, 0), (d, 0), (0, -d), (0, d)]).find(|&(dx, dy)| {
+            let (x, y) = (40 + dx, 10 + dy);
+            (-7..100).contains(&x) && (-4..30).contains(&y) && !raster.collision_check_at(&sprite, (x, y))
+        });
+        assert_eq!(truth, raster.resolve_overlap_at(&sprite, (40, 10)));
+    }
+
+    #[test]
+    fn test_collision_sweep() {
+        let raster: BinaryRaster = "
+            ........
+            ........
+            .....#..
+            ........
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert_eq!(Some((4, 1)), raster.collision_sweep(&sprite, (0, 1), (6, 1)));
+        assert_eq!(Some((4, 1)), raster.collision_sweep(&sprite, (-2, -1), (7, 2)));
+        assert_eq!(None, raster.collision_sweep(&sprite, (6, 0), (0, 0)));
+        assert_eq!(Some((5, 2)), raster.collision_sweep(&sprite, (5, 2), (5, 2)));
+    }
+
+    #[test]
+    fn test_is_rect_empty() {
+        let raster: BinaryRaster = "
+            ......
+            ....#.
+            ......
+        ".parse().unwrap();
+        assert!(raster.is_rect_empty(0, 0, 4, 3));
+        assert!(!raster.is_rect_empty(3, 1, 2, 1));
+        assert!(raster.is_rect_empty(5, 0, 10, 10));
+        assert!(raster.is_rect_empty(4, 1, 0, 5));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 200, 50, 100);
+        for _ in 0..300 {
+            let (x, y, w, h) = (rng.gen_range(0..210), rng.gen_range(0..55), rng.gen_range(0..80), rng.gen_range(0..20));
+            assert_eq!(raster.crop(x, y, w, h).count_ones() == 0, raster.is_rect_empty(x, y, w, h));
+        }
+    }
+
+    #[test]
+    fn test_raycast() {
+        let raster: BinaryRaster = "
+            ........
+            ..#.....
+            .....##.
+            ........
+        ".parse().unwrap();
+        assert_eq!(Some((5, 2)), raster.raycast((0, 2), (7, 2)));
+        assert_eq!(Some((6, 2)), raster.raycast((7, 2), (0, 2)));
+        assert_eq!(Some((2, 1)), raster.raycast((0, 0), (4, 2)));
+        assert_eq!(None, raster.raycast((0, 3), (7, 3)));
+        assert_eq!(None, raster.raycast((9, 0), (12, 2)));
+        assert_eq!(Some((2, 1)), raster.raycast((2, 1), (2, 1)));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 200, 50, 30);
+        for _ in 0..300 {
+            let from = (rng.gen_range(0..210), rng.gen_range(0..55));
+            let to = (rng.gen_range(0..210), rng.gen_range(0..55));
+            let truth = crate::draw::line_points((from.0 as isize, from.1 as isize), (to.0 as isize, to.1 as isize))
+                .map(|(x, y)| (x as usize, y as usize))
+                .find(|&(x, y)| x < raster.width() && y < raster.height() && raster.get(x, y));
+            assert_eq!(truth, raster.raycast(from, to));
+        }
+    }
+
+    #[test]
+    fn test_collide() {
+        let a: BinaryRaster = "
+            #.
+            .#
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            ##.
+            ...
+        ".parse().unwrap();
+        assert!(BinaryRaster::collide(&a, (100, -50), &b, (100, -50)));
+        assert!(BinaryRaster::collide(&b, (100, -50), &a, (100, -51)));
+        assert!(!BinaryRaster::collide(&a, (100, -50), &b, (99, -51)));
+        assert!(BinaryRaster::collide(&a, (100, -50), &b, (100, -49)));
+        assert!(!BinaryRaster::collide(&a, (100, -50), &b, (102, -50)));
+    }
+
+    #[test]
+    fn test_collision_check_many() {
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 300, 20, 8);
+        let sprite = random_raster(&mut rng, 70, 6, 4);
+        let positions: Vec<(isize, isize)> = (0..200).map(|_| (rng.gen_range(-80..310), rng.gen_range(-8..22))).collect();
+        let truth: Vec<bool> = positions.iter().map(|&pos| raster.collision_check_at(&sprite, pos)).collect();
+        assert_eq!(truth, raster.collision_check_many(&sprite, &positions));
+    }
+
+    #[test]
+    fn test_collision_check_at_masked() {
+        let raster: BinaryRaster = "
+            ##....
+            ##..#.
+            ......
+        ".parse().unwrap();
+        let ignore: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert!(raster.collision_check_at(&sprite, (-1, -1)));
+        assert!(!raster.collision_check_at_masked(&sprite, (-1, -1), &ignore));
+        assert!(!raster.collision_check_at_masked(&sprite, (1, 0), &ignore));
+        assert!(raster.collision_check_at_masked(&sprite, (3, 1), &ignore));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 200, 40, 3);
+        let ignore = random_raster(&mut rng, 150, 30, 1);
+        let sprite = random_raster(&mut rng, 70, 8, 3);
+        let kept = raster.difference(&ignore);
+        for _ in 0..300 {
+            let pos = (rng.gen_range(-75..205), rng.gen_range(-10..45));
+            assert_eq!(kept.collision_check_at(&sprite, pos), raster.collision_check_at_masked(&sprite, pos, &ignore));
+        }
+        assert_eq!(raster.collision_check_at(&sprite, (20, 5)), raster.collision_check_at_masked(&sprite, (20, 5), &BinaryRaster::new(0, 0)));
+    }
+
+    #[test]
+    fn test_first_collision_at() {
+        let raster: BinaryRaster = "
+            .....
+            ...#.
+            .#..#
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert_eq!(Some((3, 1)), raster.first_collision_at(&sprite, (2, 0)));
+        assert_eq!(Some((4, 2)), raster.first_collision_at(&sprite, (4, 1)));
+        assert_eq!(Some((1, 2)), raster.first_collision_at(&sprite, (0, 1)));
+        assert_eq!(None, raster.first_collision_at(&sprite, (-1, 1)));
+        assert_eq!(None, raster.first_collision_at(&sprite, (0, 0)));
+        let mut rng = rand::thread_rng();
+        let raster = random_raster(&mut rng, 200, 12, 20);
+        let sprite = random_raster(&mut rng, 90, 6, 3);
+        for pos in [(-10, -3), (70, 4), (150, 9), (0, 0)] {
+            let truth = (0..12).flat_map(|y| (0..200).map(move |x| (x, y))).find(|&(x, y)| {
+                let (sprite_x, sprite_y) = (x as isize - pos.0, y as isize - pos.1);
+                raster.get(x, y) && sprite_x >= 0 && sprite_y >= 0 && sprite.get(sprite_x as usize, sprite_y as usize)
+            });
+            assert_eq!(truth, raster.first_collision_at(&sprite, pos));
+        }
+    }
+
+    #[test]
+    fn test_add_from_clipped() {
+        let sprite: BinaryRaster = "
+            ##.
+            .##
+            #.#
+        ".parse().unwrap();
+        let mut raster = BinaryRaster::new(4, 3);
+        assert_eq!(0, raster.add_from_clipped(&sprite, (1, 0)));
+        assert_eq!(3, raster.add_from_clipped(&sprite, (-1, 1)));
+        assert_eq!(4, raster.add_from_clipped(&sprite, (2, 2)));
+        assert_eq!(6, raster.add_from_clipped(&sprite, (4, 0)));
+        let expected: BinaryRaster = "
+            .##.
+            #.##
+            ####
+        ".parse().unwrap();
+        assert_eq!(expected, raster);
+        let mut rng = rand::thread_rng();
+        let mut raster = random_raster(&mut rng, 150, 12, 4);
+        let sprite = random_raster(&mut rng, 80, 6, 2);
+        let mut added = raster.clone();
+        added.add_from(&sprite, (100, -2));
+        let clipped = raster.add_from_clipped(&sprite, (100, -2));
+        assert_eq!(added, raster);
+        assert_eq!(sprite.count_ones() - sprite.crop(0, 2, 50, 4).count_ones(), clipped);
+    }
+
+    #[test]
+    fn test_overlap_count_at() {
+        let raster: BinaryRaster = "
+            #..##
+            .####
+            .....
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        assert_eq!(4, raster.overlap_count_at(&sprite, (2, 0)));
+        assert_eq!(1, raster.overlap_count_at(&sprite, (4, 1)));
+        assert_eq!(0, raster.overlap_count_at(&sprite, (0, 2)));
+    }
+
+    #[test]
+    fn test_collision_wrapping() {
+        let raster: BinaryRaster = "
+            #....
+            .....
+            ....#
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ..
+        ".parse().unwrap();
+        assert!(raster.collision_check_wrapping(&sprite, (4, 0)));
+        assert!(raster.collision_check_wrapping(&sprite, (3, 2)));
+        assert!(raster.collision_check_wrapping(&sprite, (9, 5)));
+        assert!(!raster.collision_check_wrapping(&sprite, (4, 1)));
+        assert!(!raster.collision_check_wrapping(&sprite, (1, 0)));
+    }
+
+    #[test]
+    fn test_anchored() {
+        let mut raster = BinaryRaster::new(5, 5);
+        let sprite: BinaryRaster = "
+            ###
+            #.#
+        ".parse().unwrap();
+        raster.add_from_anchored(&sprite, (2, 2), Anchor::Center);
+        let truth: BinaryRaster = "
+            .....
+            .###.
+            .#.#.
+            .....
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        assert!(raster.collision_check_anchored(&sprite, (3, 3), Anchor::BottomRight));
+        assert_eq!(Ok(()), raster.add_from_checked_anchored(&sprite, (4, 4), Anchor::BottomRight));
+        assert!(raster.get(4, 4) && raster.get(2, 3));
+    }
+
     #[test]
     fn test_bound_check() {
         let mut rng = rand::thread_rng();
@@ -216,6 +1483,201 @@ mod tests {
         assert!(!main_raster.can_fit(&other_raster, (10, 18)));
     }
 
+    #[test]
+    fn test_bounding_box() {
+        let raster = BinaryRaster::from_raster(&[
+            0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0,
+            0, 0, 0, 0, 0,
+        ], 5);
+        assert_eq!(Some((1, 1, 3, 3)), raster.bounding_box());
+        assert_eq!(None, BinaryRaster::new(5, 5).bounding_box());
+    }
+
+    #[test]
+    fn test_crop_to_content() {
+        let raster = BinaryRaster::from_raster(&[
+            0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 1, 0, 0, 0,
+            0, 0, 0, 1, 0,
+            0, 0, 0, 0, 0,
+        ], 5);
+        let cropped = BinaryRaster::from_raster(&[
+            0, 1, 0,
+            1, 0, 0,
+            0, 0, 1,
+        ], 3);
+        assert_eq!((cropped, (1, 1)), raster.crop_to_content());
+    }
+
+    #[test]
+    fn test_crop() {
+        let raster = BinaryRaster::from_raster(&[
+            0, 1, 0, 1, 0,
+            0, 1, 0, 1, 0,
+            0, 0, 0, 0, 0,
+            1, 0, 0, 0, 1,
+            0, 1, 1, 1, 0,
+        ], 5);
+        let cropped = BinaryRaster::from_raster(&[
+            1, 0, 1,
+            0, 0, 0,
+        ], 3);
+        assert_eq!(cropped, raster.crop(1, 1, 3, 2));
+        // the rectangle is clipped to the raster
+        let clipped = BinaryRaster::from_raster(&[
+            0, 1,
+            1, 0,
+        ], 2);
+        assert_eq!(clipped, raster.crop(3, 3, 10, 10));
+        assert_eq!(0, raster.crop(5, 0, 2, 2).width());
+    }
+
+    #[test]
+    fn test_toggle_rect() {
+        let mut raster: BinaryRaster = "
+            ##...
+            .#.#.
+            .....
+        ".parse().unwrap();
+        raster.toggle_rect(1, 1, 10, 5);
+        let truth: BinaryRaster = "
+            ##...
+            ..#.#
+            .####
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_padded() {
+        let raster = BinaryRaster::from_raster(&[
+            1, 1,
+            0, 1,
+        ], 2);
+        let padded = BinaryRaster::from_raster(&[
+            0, 0, 0, 0, 0,
+            0, 1, 1, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0,
+        ], 5);
+        assert_eq!(padded, raster.padded(1, 2, 1, 2));
+        let wide = raster.padded(0, 100, 0, 0);
+        assert_eq!(102, wide.width());
+        assert!(wide.get_display(1).split('\n').all(|row| row.chars().count() == 102 * BIT_0.chars().count()));
+    }
+
+    #[test]
+    fn test_resized() {
+        let raster = BinaryRaster::from_raster(&[
+            1, 1, 0,
+            0, 1, 0,
+            0, 0, 1,
+        ], 3);
+        let grown = BinaryRaster::from_raster(&[
+            0, 0, 0, 0, 0,
+            0, 1, 1, 0, 0,
+            0, 0, 1, 0, 0,
+            0, 0, 0, 1, 0,
+            0, 0, 0, 0, 0,
+        ], 5);
+        assert_eq!(grown, raster.resized(5, 5, Anchor::Center));
+        let shrunk = BinaryRaster::from_raster(&[
+            1, 0,
+            0, 1,
+        ], 2);
+        assert_eq!(shrunk, raster.resized(2, 2, Anchor::BottomRight));
+        let mixed = BinaryRaster::from_raster(&[
+            1, 1, 0, 0,
+            0, 1, 0, 0,
+        ], 4);
+        assert_eq!(mixed, raster.resized(4, 2, Anchor::TopLeft));
+    }
+
+    #[test]
+    fn test_fill_clear() {
+        let mut raster = BinaryRaster::new(3, 2);
+        raster.fill();
+        assert_eq!(BinaryRaster::from_raster(&[1; 6], 3), raster);
+        raster.clear();
+        assert_eq!(BinaryRaster::new(3, 2), raster);
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let a: BinaryRaster = "
+            ##.
+            #..
+            ...
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            .#
+            ##
+        ".parse().unwrap();
+        let a_and_not_b: BinaryRaster = "
+            #..
+            ...
+            ...
+        ".parse().unwrap();
+        assert_eq!(a_and_not_b, a.zip_with(&b, |a, b| a && !b));
+        assert_eq!(a.inverted(), a.map(|a| !a));
+    }
+
+    #[test]
+    fn test_differences() {
+        let a: BinaryRaster = "
+            ##.
+            #.#
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            .#.#
+            ##..
+            ####
+        ".parse().unwrap();
+        let difference: BinaryRaster = "
+            #..
+            ..#
+        ".parse().unwrap();
+        let symmetric_difference: BinaryRaster = "
+            #..
+            .##
+        ".parse().unwrap();
+        assert_eq!(difference, a.difference(&b));
+        assert_eq!(symmetric_difference, a.symmetric_difference(&b));
+    }
+
+    #[test]
+    fn test_fill_clear_rect() {
+        let mut raster = BinaryRaster::new(5, 4);
+        raster.fill_rect(1, 1, 10, 2);
+        raster.clear_rect(2, 0, 1, 2);
+        let truth = BinaryRaster::from_raster(&[
+            0, 0, 0, 0, 0,
+            0, 1, 0, 1, 1,
+            0, 1, 1, 1, 1,
+            0, 0, 0, 0, 0,
+        ], 5);
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_inverted() {
+        let raster = BinaryRaster::from_raster(&[
+            0, 1, 0,
+            1, 1, 0,
+        ], 3);
+        let inverted = BinaryRaster::from_raster(&[
+            1, 0, 1,
+            0, 0, 1,
+        ], 3);
+        assert_eq!(inverted, raster.inverted());
+        assert_eq!(raster, raster.inverted().inverted());
+    }
+
     #[test]
     fn test_display() {
         println!("{}", BinaryRaster::from_raster(&vec![
@@ -1,35 +1,45 @@
 use core::fmt::Debug;
-const BIT_1: &str = "██";
-const BIT_0: &str = "  ";
+use std::sync::{Arc, LazyLock, OnceLock};
+/// How a bit with a value of 1 is displayed
+pub const BIT_1: &str = "██";
+/// How a bit with a value of 0 is displayed
+pub const BIT_0: &str = "  ";
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct BitLine {
-    data: Vec<usize>,
+/// A line with no bits, for when a line is missing
+pub(crate) static EMPTY_LINE: LazyLock<BitLine> = LazyLock::new(|| BitLine::new(0));
+
+/// A line of bits packed in usizes
+/// The chunks are shared between clones of the line until one of them is modified
+#[derive(Debug, Clone, Eq)]
+pub struct BitLine {
+    data: Arc<Vec<usize>>,
     pub(crate) bits: usize,
+    /// The positions of the first and last bits with a value of 1, computed on first use and reset when the bits change
+    extent: OnceLock<Option<(usize, usize)>>,
 }
 
 impl BitLine {
     /// Turns a "continuous" position i into a "chunked" position i
     /// returning the index of the u64 and the position of the bit inside that u64
-    pub fn chunked(i: usize) -> (usize, u32) {
+    pub(crate) fn chunked(i: usize) -> (usize, usize) {
         (
             i / usize::BITS as usize,
-            i as u32 % usize::BITS
+            i % usize::BITS as usize
         )
     }
 
     /// How many u64 are needed to store this amount of bits ? 
-    pub fn chunks_to_fit(bits: usize) -> usize {
-        bits / usize::BITS as usize + if bits % usize::BITS as usize == 0 { 0 } else { 1 }
+    pub(crate) fn chunks_to_fit(bits: usize) -> usize {
+        bits.div_ceil(usize::BITS as usize)
     }
 
     pub fn new(bits: usize) -> Self {
-        Self { data: vec![0; BitLine::chunks_to_fit(bits)], bits }
+        Self { data: Arc::new(vec![0; BitLine::chunks_to_fit(bits)]), bits, extent: OnceLock::new() }
     }
 
     pub fn from_bits(bits: &[u8]) -> Self {
         let chunkslen = BitLine::chunks_to_fit(bits.len());
-        let mut data = vec![0; chunkslen as usize];
+        let mut data = vec![0; chunkslen];
         let mut chunk_i = 0;
         let mut bit_i = 0;
         for &bit in bits {
@@ -42,42 +52,147 @@ impl BitLine {
                 chunk_i += 1;
             }
         }
-        Self { data, bits: bits.len() }
+        Self { data: Arc::new(data), bits: bits.len(), extent: OnceLock::new() }
     }
 
-    pub fn to_bits(&self) -> Vec<u8> {
-        let mut res = Vec::with_capacity(self.bits);
-        for (seg_i, &segment) in self.data.iter().enumerate() {
-            for bit_i in 0..usize::BITS as usize {
-                if seg_i*usize::BITS as usize + bit_i >= self.bits {
-                    return res;
-                }
-                res.push(((segment >> bit_i) & 1) as u8);
+    /// The amount of bits in the line
+    pub fn len(&self) -> usize {
+        self.bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Builds a bitline of the given length where the bit i is f(i)
+    pub fn from_fn(bits: usize, mut f: impl FnMut(usize) -> bool) -> Self {
+        let mut data = vec![0; BitLine::chunks_to_fit(bits)];
+        for i in 0..bits {
+            if f(i) {
+                let (chunk_i, bit_i) = BitLine::chunked(i);
+                data[chunk_i] |= 1 << bit_i;
             }
         }
-        res
+        Self { data: Arc::new(data), bits, extent: OnceLock::new() }
+    }
+
+    /// The value of the bit i, false if i is past the end of the line
+    pub fn get(&self, i: usize) -> bool {
+        let (chunk_i, bit_i) = BitLine::chunked(i);
+        i < self.bits && (self.chunk(chunk_i) >> bit_i) & 1 == 1
+    }
+
+    /// Sets the value of the bit i, does nothing if i is past the end of the line
+    pub fn set(&mut self, i: usize, value: bool) {
+        if i >= self.bits || self.get(i) == value {
+            return;
+        }
+        self.allocate_chunks();
+        let (chunk_i, bit_i) = BitLine::chunked(i);
+        if value {
+            self.data_mut()[chunk_i] |= 1 << bit_i;
+        } else {
+            self.data_mut()[chunk_i] &= !(1 << bit_i);
+        }
+    }
+
+    pub fn to_bits(&self) -> Vec<u8> {
+        // the chunks past the allocated ones (after a pad or a shift) are read as 0
+        (0..self.bits).map(|i| {
+            let (chunk_i, bit_i) = BitLine::chunked(i);
+            ((self.chunk(chunk_i) >> bit_i) & 1) as u8
+        }).collect()
     }
 
     /// The position of the first bit with a value of 1 in the line
     pub fn start(&self) -> Option<usize> {
-        for (i, segment) in self.data.iter().enumerate() {
-            let trailing_zeros = segment.trailing_zeros();
-            if trailing_zeros < usize::BITS {
-                return Some(i*usize::BITS as usize + trailing_zeros as usize)
-            }
-        }
-        None
+        self.extent().map(|(start, _)| start)
     }
 
     /// The position of the last bit with a value of 1 in the line
     pub fn end(&self) -> Option<usize> {
-        for (i, segment) in self.data.iter().enumerate().rev() {
-            let leading_zeros = segment.leading_zeros();
-            if leading_zeros < usize::BITS {
-                return Some((i+1)*usize::BITS as usize - leading_zeros as usize - 1)
+        self.extent().map(|(_, end)| end)
+    }
+
+    /// The positions of the first and last bits with a value of 1 in the line, cached until the line is modified
+    pub(crate) fn extent(&self) -> Option<(usize, usize)> {
+        *self.extent.get_or_init(|| {
+            let first = self.data.iter().position(|&chunk| chunk != 0)?;
+            let last = self.data.iter().rposition(|&chunk| chunk != 0)?;
+            Some((
+                first * usize::BITS as usize + self.data[first].trailing_zeros() as usize,
+                (last + 1) * usize::BITS as usize - self.data[last].leading_zeros() as usize - 1,
+            ))
+        })
+    }
+
+    /// The chunks of the line for modifying them, copying them if they're shared and resetting the cached extent
+    fn data_mut(&mut self) -> &mut Vec<usize> {
+        self.extent.take();
+        Arc::make_mut(&mut self.data)
+    }
+
+    /// The position of the first bit with a value of 1 at or after i
+    pub fn next_one(&self, i: usize) -> Option<usize> {
+        if i >= self.bits {
+            return None;
+        }
+        let (mut chunk_i, bit_i) = BitLine::chunked(i);
+        let mut chunk = self.chunk(chunk_i) & (usize::MAX << bit_i);
+        while chunk == 0 {
+            chunk_i += 1;
+            if chunk_i >= self.data.len() {
+                return None;
             }
+            chunk = self.data[chunk_i];
+        }
+        Some(chunk_i * usize::BITS as usize + chunk.trailing_zeros() as usize)
+    }
+
+    /// The position of the first bit with a value of 0 at or after i, or the length of the line if there's none
+    pub fn next_zero(&self, i: usize) -> usize {
+        if i >= self.bits {
+            return self.bits;
         }
-        None
+        let (mut chunk_i, bit_i) = BitLine::chunked(i);
+        let mut chunk = !self.chunk(chunk_i) & (usize::MAX << bit_i);
+        while chunk == 0 {
+            chunk_i += 1;
+            chunk = !self.chunk(chunk_i);
+        }
+        (chunk_i * usize::BITS as usize + chunk.trailing_zeros() as usize).min(self.bits)
+    }
+
+    /// The position of the last bit with a value of 0 at or before i (or before the end of the line), None if there's none
+    pub(crate) fn prev_zero(&self, i: usize) -> Option<usize> {
+        self.prev_matching(i, usize::MAX)
+    }
+
+    /// The position of the last bit with a value of 1 at or before i (or before the end of the line), None if there's none
+    pub(crate) fn prev_one(&self, i: usize) -> Option<usize> {
+        self.prev_matching(i, 0)
+    }
+
+    /// The position of the last bit at or before i that is set in the chunks xored with flip
+    fn prev_matching(&self, i: usize, flip: usize) -> Option<usize> {
+        let i = i.min(self.bits.checked_sub(1)?);
+        let (mut chunk_i, bit_i) = BitLine::chunked(i);
+        let mut chunk = (self.chunk(chunk_i) ^ flip) & (usize::MAX >> (usize::BITS as usize - 1 - bit_i));
+        while chunk == 0 {
+            chunk_i = chunk_i.checked_sub(1)?;
+            chunk = self.chunk(chunk_i) ^ flip;
+        }
+        Some((chunk_i + 1) * usize::BITS as usize - 1 - chunk.leading_zeros() as usize)
+    }
+
+    /// The ranges start..end of consecutive bits with a value of 1, in order
+    pub fn runs(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut i = 0;
+        std::iter::from_fn(move || {
+            let start = self.next_one(i)?;
+            i = self.next_zero(start);
+            Some((start, i))
+        })
     }
 
     /// end - start + 1 or 0 if the line is empty
@@ -88,39 +203,336 @@ impl BitLine {
         end - self.start().unwrap() + 1
     }
 
+    /// The amount of bits with a value of 1 in the line
+    pub fn count_ones(&self) -> usize {
+        self.data.iter().map(|chunk| chunk.count_ones() as usize).sum()
+    }
+
+    /// Pairs of chunks of self and other at the same index, chunks past the end of either line are read as 0
+    pub(crate) fn chunk_pairs<'a>(&'a self, other: &'a BitLine) -> impl Iterator<Item = (usize, usize)> + 'a {
+        (0..self.data.len().max(other.data.len())).map(|i| (self.chunk(i), other.chunk(i)))
+    }
+
     /// The amount of usize that are used to represent the bitline
-    pub fn chunk_width(&self) -> usize {
+    pub(crate) fn chunk_width(&self) -> usize {
         self.data.len()
     }
 
-    /// Shifts the bits of the bitline to the right, assumes the shifting amount is less than usize::BITS (32 or 64)
-    pub fn shifted_right(&self, amount: u32) -> BitLine {
+    /// The chunk at index i, or 0 if it's not allocated
+    pub(crate) fn chunk(&self, i: usize) -> usize {
+        self.data.get(i).copied().unwrap_or(0)
+    }
+
+    /// The usize::BITS bits starting at the bit start, in the order of a chunk, the bits past the end of the line are read as 0
+    pub(crate) fn word_at(&self, start: usize) -> usize {
+        let (chunk_i, shift_amount) = BitLine::chunked(start);
+        if shift_amount == 0 {
+            self.chunk(chunk_i)
+        } else {
+            self.chunk(chunk_i) >> shift_amount | self.chunk(chunk_i + 1) << (usize::BITS as usize - shift_amount)
+        }
+    }
+
+    /// Replaces the chunk at index i, bits past the end of the line are ignored
+    pub(crate) fn set_chunk(&mut self, i: usize, chunk: usize) {
+        let (last_i, bit_i) = BitLine::chunked(self.bits);
+        let chunk = if i == last_i { chunk & !(usize::MAX << bit_i) } else { chunk };
+        // writing the current value keeps the chunks shared
+        if i > last_i || self.chunk(i) == chunk {
+            return;
+        }
+        self.allocate_chunks();
+        self.data_mut()[i] = chunk;
+    }
+
+    /// Sets every bit past self.bits to 0
+    /// The chunks are only touched if a padding bit is set, so shared chunks aren't copied for nothing
+    fn clear_padding(&mut self) {
+        let (chunk_i, bit_i) = BitLine::chunked(self.bits);
+        let padding = |i: usize| if i == chunk_i { usize::MAX << bit_i } else { usize::MAX };
+        let dirty = self.data.iter().enumerate().skip(chunk_i).any(|(i, &chunk)| chunk & padding(i) != 0);
+        if dirty {
+            for (i, chunk) in self.data_mut().iter_mut().enumerate().skip(chunk_i) {
+                *chunk &= !padding(i);
+            }
+        }
+    }
+
+    /// Allocates the chunks needed to store every bit of the line
+    fn allocate_chunks(&mut self) {
+        let chunks = BitLine::chunks_to_fit(self.bits);
+        if self.data.len() < chunks {
+            self.data_mut().resize(chunks, 0);
+        }
+    }
+
+    /// Sets every bit of the line to 0
+    pub fn clear(&mut self) {
+        if self.data.iter().all(|&chunk| chunk == 0) {
+            return;
+        }
+        self.data_mut().fill(0);
+    }
+
+    /// Sets every bit of the line to 1
+    pub fn fill(&mut self) {
+        self.allocate_chunks();
+        self.data_mut().fill(usize::MAX);
+        self.clear_padding();
+    }
+
+    /// Flips every bit of the line
+    pub fn invert(&mut self) {
+        self.allocate_chunks();
+        for chunk in self.data_mut().iter_mut() {
+            *chunk = !*chunk;
+        }
+        self.clear_padding();
+    }
+
+    /// Combines every bit of self with the bit of other at the same position using f, bits past the end of other are read as 0
+    /// f is only evaluated on the 4 possible inputs and the result is computed chunk by chunk
+    pub fn zip_with(&self, other: &BitLine, f: impl Fn(bool, bool) -> bool) -> BitLine {
+        let table = [(false, false), (false, true), (true, false), (true, true)].map(|(a, b)| f(a, b));
+        let mut res = BitLine::new(self.bits);
+        for (i, chunk) in res.data_mut().iter_mut().enumerate() {
+            let (a, b) = (self.chunk(i), other.chunk(i));
+            let minterms = [!a & !b, !a & b, a & !b, a & b];
+            *chunk = minterms.into_iter().zip(table).filter(|&(_, set)| set).fold(0, |acc, (minterm, _)| acc | minterm);
+        }
+        res.clear_padding();
+        res
+    }
+
+    /// Replaces every chunk of self with op(chunk of self, chunk of other), chunks past the end of other are read as 0
+    pub(crate) fn combine_with(&mut self, other: &BitLine, op: impl Fn(usize, usize) -> usize) {
+        self.allocate_chunks();
+        let Some(first) = self.data.iter().enumerate().position(|(i, &chunk)| op(chunk, other.chunk(i)) != chunk) else {
+            return;
+        };
+        for (i, chunk) in self.data_mut().iter_mut().enumerate().skip(first) {
+            *chunk = op(*chunk, other.chunk(i));
+        }
+        self.clear_padding();
+    }
+
+    /// Replaces every chunk of self covered by source placed at segment_offset with op(chunk of self, chunk of source),
+    /// the parts of source that go past the end of self are ignored
+    pub(crate) fn combine_at(&mut self, source: &BitLine, segment_offset: usize, op: impl Fn(usize, usize) -> usize) {
+        self.allocate_chunks();
+        // the chunks are only copied (if shared) and the extent reset from the first chunk that changes
+        let Some(first) = self.data.iter().skip(segment_offset).zip(source.data.iter())
+            .position(|(&chunk, &source_chunk)| op(chunk, source_chunk) != chunk) else {
+            return;
+        };
+        let chunks = self.data_mut().iter_mut().skip(segment_offset + first);
+        for (chunk, &source_chunk) in chunks.zip(source.data.iter().skip(first)) {
+            *chunk = op(*chunk, source_chunk);
+        }
+        self.clear_padding();
+    }
+
+    /// Applies f to every bit of the line, f is only evaluated on the 2 possible inputs
+    pub fn map(&self, f: impl Fn(bool) -> bool) -> BitLine {
+        self.zip_with(&BitLine::new(0), |a, _| f(a))
+    }
+
+    /// The chunks covering the bits in start..end, along with the mask of these bits in each chunk
+    fn range_masks(start: usize, end: usize) -> impl Iterator<Item = (usize, usize)> {
+        let (first_chunk, first_bit) = BitLine::chunked(start);
+        let (last_chunk, last_bit) = BitLine::chunked(end);
+        (first_chunk..=last_chunk).filter_map(move |i| {
+            let mut mask = usize::MAX;
+            if i == first_chunk {
+                mask &= usize::MAX << first_bit;
+            }
+            if i == last_chunk {
+                mask &= (1 << last_bit) - 1;
+            }
+            (mask != 0).then_some((i, mask))
+        })
+    }
+
+    /// Applies op to every chunk covering the bits in start..end (clipped to the line) with the mask of these bits
+    fn apply_range(&mut self, start: usize, end: usize, op: impl Fn(&mut usize, usize)) {
+        self.allocate_chunks();
+        let end = end.min(self.bits);
+        let data = self.data_mut();
+        for (i, mask) in BitLine::range_masks(start, end) {
+            op(&mut data[i], mask);
+        }
+    }
+
+    /// The amount of bits with a value of 1 in start..end
+    pub fn count_ones_range(&self, start: usize, end: usize) -> usize {
+        BitLine::range_masks(start, end.min(self.bits))
+            .map(|(i, mask)| (self.chunk(i) & mask).count_ones() as usize)
+            .sum()
+    }
+
+    /// True if every bit in start..end has a value of 0, stopping at the first chunk with a bit of value 1
+    pub fn is_range_empty(&self, start: usize, end: usize) -> bool {
+        BitLine::range_masks(start, end.min(self.bits)).all(|(i, mask)| self.chunk(i) & mask == 0)
+    }
+
+    /// Sets the bits in start..end to 1
+    pub fn fill_range(&mut self, start: usize, end: usize) {
+        self.apply_range(start, end, |chunk, mask| *chunk |= mask);
+    }
+
+    /// Sets the bits in start..end to 0
+    pub fn clear_range(&mut self, start: usize, end: usize) {
+        self.apply_range(start, end, |chunk, mask| *chunk &= !mask);
+    }
+
+    /// Flips the bits in start..end
+    pub fn toggle_range(&mut self, start: usize, end: usize) {
+        self.apply_range(start, end, |chunk, mask| *chunk ^= mask);
+    }
+
+    /// Extracts len bits starting at position start into a new bitline, bits past the end of self are read as 0
+    pub fn sub_line(&self, start: usize, len: usize) -> BitLine {
+        let mut res = BitLine::new(len);
+        let (segment_offset, shift_amount) = BitLine::chunked(start);
+        for (i, chunk) in res.data_mut().iter_mut().enumerate() {
+            *chunk = self.chunk(i + segment_offset) >> shift_amount;
+            if shift_amount != 0 {
+                *chunk |= self.chunk(i + segment_offset + 1) << (usize::BITS as usize - shift_amount);
+            }
+        }
+        res.clear_padding();
+        res
+    }
+
+    /// Returns a copy of the bitline with the order of its bits reversed
+    pub fn reversed(&self) -> BitLine {
+        let chunks = BitLine::chunks_to_fit(self.bits);
+        let full = BitLine {
+            data: Arc::new((0..chunks).rev().map(|i| self.chunk(i).reverse_bits()).collect()),
+            bits: chunks * usize::BITS as usize,
+            extent: OnceLock::new(),
+        };
+        full.sub_line(full.bits - self.bits, self.bits)
+    }
+
+    /// Returns a copy of the bitline with every bit repeated factor times
+    pub fn scaled_up(&self, factor: usize) -> BitLine {
+        let mut res = BitLine::new(self.bits * factor);
+        for (start, end) in self.runs() {
+            res.fill_range(start * factor, end * factor);
+        }
+        res
+    }
+
+    /// A bitline of the given length made of self repeated, a line of 0s if self is empty
+    pub fn repeated(&self, len: usize) -> BitLine {
+        if self.bits == 0 {
+            return BitLine::new(len);
+        }
+        // doubles the pattern until it's long enough so the copies are done a whole chunk at a time
+        let mut res = self.clone();
+        while res.bits < len {
+            let mut doubled = res.padded(0, res.bits);
+            doubled.combine_with(&res.padded(res.bits, 0), |a, b| a | b);
+            res = doubled;
+        }
+        res.sub_line(0, len)
+    }
+
+    /// Returns a copy of the bitline with left and right bits with a value of 0 added on each side
+    pub fn padded(&self, left: usize, right: usize) -> BitLine {
+        let mut res = self.shifted_right(left);
+        res.bits += right;
+        res
+    }
+
+    /// Shifts the bits of the bitline to the right, growing it by amount bits
+    /// Whole chunks are moved first, then the remaining amount (less than usize::BITS) is shifted bit-wise
+    pub fn shifted_right(&self, amount: usize) -> BitLine {
         if amount == 0 {
             return self.clone();
         }
-        debug_assert!(amount < usize::BITS);
-        let mut res = Vec::with_capacity(self.data.len()+1);
-        self.data.clone_into(&mut res);
-        for i in (1..=res.len()).rev() {
-            res[i-1] <<= amount;
-            let spill = self.data[i-1] >> (usize::BITS-amount);
-            if spill == 0 {
-                continue;
+        let (whole_chunks, amount) = BitLine::chunked(amount);
+        let mut res = Vec::with_capacity(whole_chunks + self.data.len() + 1);
+        res.resize(whole_chunks, 0);
+        if amount == 0 {
+            res.extend_from_slice(&self.data);
+        } else {
+            let mut spill = 0;
+            for &chunk in self.data.iter() {
+                res.push((chunk << amount) | spill);
+                spill = chunk >> (usize::BITS as usize - amount);
             }
-            if i < res.len() {
-                res[i] |= spill;
-            } else {
+            if spill != 0 {
                 res.push(spill);
             }
         }
         BitLine {
-            data: res,
-            bits: self.bits + amount as usize,
+            data: Arc::new(res),
+            bits: self.bits + whole_chunks * usize::BITS as usize + amount,
+            extent: OnceLock::new(),
         }
     }
 
+    /// Shifts the bits of the bitline to the left, dropping the first amount bits
+    pub fn shifted_left(&self, amount: usize) -> BitLine {
+        self.sub_line(amount, self.bits.saturating_sub(amount))
+    }
+
+    /// Returns a copy of the bitline where every bit within radius of a bit with a value of 1 is set to 1
+    pub(crate) fn dilated(&self, radius: usize) -> BitLine {
+        self.spread(radius, |a, b| a | b)
+    }
+
+    /// Returns a copy of the bitline where every bit within radius of a bit with a value of 0 is set to 0,
+    /// bits outside of the bitline being read as 0
+    pub(crate) fn eroded(&self, radius: usize) -> BitLine {
+        self.spread(radius, |a, b| a & b)
+    }
+
+    /// Combines every bit with the bits within radius of it using op
+    fn spread(&self, radius: usize, op: impl Fn(usize, usize) -> usize + Copy) -> BitLine {
+        let mut res = self.clone();
+        let mut reach = 0;
+        // the reach doubles at every step so it takes log(radius) shifts
+        while reach < radius {
+            let step = (reach + 1).min(radius - reach);
+            res.combine_with(&res.shifted_right(step), op);
+            res.combine_with(&res.shifted_left(step), op);
+            reach += step;
+        }
+        res
+    }
+
+    /// Shifts the bits of the bitline to the right by amount (to the left if negative),
+    /// the bits going past one end coming back at the other end
+    pub fn shifted_wrapping(&self, amount: isize) -> BitLine {
+        if self.bits == 0 {
+            return self.clone();
+        }
+        let amount = amount.rem_euclid(self.bits as isize) as usize;
+        let mut res = self.wrapped(self.bits, amount);
+        res.bits = self.bits;
+        res
+    }
+
+    /// A bitline of the given width where every bit i of self is ORed at position (x + i) % width
+    pub(crate) fn wrapped(&self, width: usize, x: usize) -> BitLine {
+        if width == 0 {
+            return BitLine::new(0);
+        }
+        let x = x % width;
+        let mut res = self.sub_line(0, width - x).padded(x, 0);
+        let mut start = width - x;
+        while start < self.bits {
+            res.combine_with(&self.sub_line(start, width), |a, b| a | b);
+            start += width;
+        }
+        res
+    }
+
     /// Checks if other have 1 bit in common with self at the given offset
-    pub fn collision_check(&self, other: &BitLine, segment_offset: usize) -> bool {
+    pub(crate) fn collision_check(&self, other: &BitLine, segment_offset: usize) -> bool {
         if segment_offset >= self.data.len() {
             return false;
         }
@@ -133,12 +545,21 @@ impl BitLine {
         false
     }
 
-    /// Add the entire source to self at the given offset, assuming it fits
-    pub fn add_from(&mut self, source: &BitLine, segment_offset: usize) {
-        debug_assert!(source.data.len()+segment_offset <= self.data.len());
-        for i in 0..source.data.len() {
-            self.data[i+segment_offset] |= source.data[i];
-        }
+    /// The position in self of the first bit with a value of 1 that other has in common with self at the given offset
+    pub(crate) fn first_collision(&self, other: &BitLine, segment_offset: usize) -> Option<usize> {
+        self.data.iter().skip(segment_offset).zip(other.data.iter())
+            .position(|(a, b)| a & b != 0)
+            .map(|i| {
+                let chunk_i = i + segment_offset;
+                chunk_i * usize::BITS as usize + (self.data[chunk_i] & other.data[i]).trailing_zeros() as usize
+            })
+    }
+
+    /// Counts the bits with a value of 1 that other has in common with self at the given offset
+    pub(crate) fn overlap_count(&self, other: &BitLine, segment_offset: usize) -> usize {
+        self.data.iter().skip(segment_offset).zip(other.data.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
     }
 
     /// Gets a String display of the bitline at the desired resolution, with "■" for 1 and " " for 0
@@ -156,8 +577,17 @@ impl BitLine {
     }
 }
 
+/// Bitlines are equal if they have the same bits, regardless of how many chunks are allocated to store them
+impl PartialEq for BitLine {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits && self.chunk_pairs(other).all(|(a, b)| a == b)
+    }
+}
+
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 mod tests {
+    use std::sync::Arc;
     use super::{BitLine, BIT_0, BIT_1};
     use rand::Rng;
 
@@ -169,6 +599,44 @@ mod tests {
         assert_eq!(truth, bitline.to_bits());
     }
 
+    #[test]
+    fn test_from_fn() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..100).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        assert_eq!(BitLine::from_bits(&truth), BitLine::from_fn(truth.len(), |i| truth[i] == 1));
+    }
+
+    #[test]
+    fn test_extent_cache() {
+        let mut bitline = BitLine::new(200);
+        assert_eq!((None, None), (bitline.start(), bitline.end()));
+        bitline.set(70, true);
+        assert_eq!((Some(70), Some(70)), (bitline.start(), bitline.end()));
+        bitline.fill_range(100, 150);
+        assert_eq!((Some(70), Some(149)), (bitline.start(), bitline.end()));
+        bitline.combine_with(&BitLine::from_fn(200, |i| i == 3), |a, b| a | b);
+        assert_eq!(Some(3), bitline.start());
+        bitline.set_chunk(0, 0);
+        assert_eq!(Some(70), bitline.start());
+        bitline.invert();
+        assert_eq!((Some(0), Some(199)), (bitline.start(), bitline.end()));
+        bitline.clear();
+        assert_eq!(None, bitline.start());
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut bitline = BitLine::new(100);
+        bitline.set(70, true);
+        bitline.set(100, true);
+        assert!(bitline.get(70));
+        assert!(!bitline.get(69));
+        assert!(!bitline.get(100));
+        assert_eq!(1, bitline.count_ones());
+        bitline.set(70, false);
+        assert!(!bitline.get(70));
+    }
+
     fn collision_check(a: &[u8], b: &[u8]) -> bool {
         BitLine::from_bits(a).collision_check(&BitLine::from_bits(b), 0)
     }
@@ -187,6 +655,14 @@ mod tests {
         assert!(should_be_true);
     }
 
+    #[test]
+    fn test_overlap_count() {
+        let a = BitLine::from_bits(&[0, 1, 1, 0, 1, 0]);
+        let b = BitLine::from_bits(&[1, 1, 0, 0, 1, 1]);
+        assert_eq!(2, a.overlap_count(&b, 0));
+        assert_eq!(0, a.overlap_count(&b, 1));
+    }
+
     #[test]
     fn test_shift() {
         let shift_amount = 5;
@@ -195,8 +671,87 @@ mod tests {
         let mut shifted_truth = vec![0; truth.len()+shift_amount];
         shifted_truth[shift_amount..].copy_from_slice(&truth);
         let bitline = BitLine::from_bits(&truth);
-        let shifted_bitline = bitline.shifted_right(shift_amount as u32);
+        let shifted_bitline = bitline.shifted_right(shift_amount);
         assert_eq!(shifted_truth, shifted_bitline.to_bits());
+        // shifting by more than usize::BITS
+        let mut shifted_truth = vec![0; 150];
+        shifted_truth.extend_from_slice(&truth);
+        assert_eq!(shifted_truth, bitline.shifted_right(150).to_bits());
+        shifted_truth.drain(..22);
+        assert_eq!(shifted_truth, bitline.shifted_right(128).to_bits());
+    }
+
+    #[test]
+    fn test_shift_left() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..200).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let bitline = BitLine::from_bits(&truth);
+        assert_eq!(truth[5..].to_vec(), bitline.shifted_left(5).to_bits());
+        assert_eq!(truth[130..].to_vec(), bitline.shifted_left(130).to_bits());
+        assert_eq!(0, bitline.shifted_left(300).len());
+    }
+
+    #[test]
+    fn test_shifted_wrapping() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..200).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let bitline = BitLine::from_bits(&truth);
+        let mut rotated = truth.clone();
+        rotated.rotate_right(70);
+        assert_eq!(rotated, bitline.shifted_wrapping(70).to_bits());
+        assert_eq!(rotated, bitline.shifted_wrapping(-130).to_bits());
+        let wrapped = BitLine::from_bits(&[1, 1, 0, 0, 1, 1, 1]).wrapped(3, 1);
+        assert_eq!(vec![1, 1, 1], wrapped.to_bits());
+    }
+
+    #[test]
+    fn test_shared_chunks() {
+        let line = BitLine::from_fn(200, |i| i % 3 == 0);
+        let mut copy = line.clone();
+        // operations that don't change any bit keep sharing the chunks
+        copy.combine_at(&BitLine::new(100), 1, |a, b| a | b);
+        copy.combine_with(&line, |a, b| a | b);
+        assert!(Arc::ptr_eq(&line.data, &copy.data));
+        copy.set_chunk(1, line.chunk(1));
+        copy.set_chunk(3, line.chunk(3) | usize::MAX << 8);
+        assert!(Arc::ptr_eq(&line.data, &copy.data));
+        let mut empty = BitLine::new(200);
+        let empty_copy = empty.clone();
+        empty.clear();
+        assert!(Arc::ptr_eq(&empty.data, &empty_copy.data));
+        copy.set(3, true);
+        assert!(Arc::ptr_eq(&line.data, &copy.data));
+        copy.set(4, true);
+        assert!(!Arc::ptr_eq(&line.data, &copy.data));
+        assert!(!line.get(4) && copy.get(4));
+        // stamping a sprite only copies the lines where it has bits with a value of 1
+        let mut raster = crate::BinaryRaster::from_fn(200, 4, |x, y| (x + y) % 5 == 0);
+        let snapshot = raster.clone();
+        let sprite = crate::BinaryRaster::from_fn(10, 3, |_, y| y == 1);
+        raster.add_from(&sprite, (-3, 0));
+        let shared: Vec<bool> = raster.0.iter().zip(&snapshot.0).map(|(a, b)| Arc::ptr_eq(&a.data, &b.data)).collect();
+        assert_eq!(vec![true, false, true, true], shared);
+    }
+
+    #[test]
+    fn test_eq_ignores_allocation() {
+        // padding on the right grows the line without allocating the chunks of the new bits
+        let mut truth = vec![1; 10];
+        let padded = BitLine::from_bits(&truth).padded(0, 100);
+        truth.resize(110, 0);
+        let allocated = BitLine::from_bits(&truth);
+        assert_ne!(padded.data.len(), allocated.data.len());
+        assert_eq!(allocated, padded);
+        assert_eq!(padded, allocated);
+        // a shift that only spills 0s doesn't allocate the last chunk
+        let mut truth = vec![0; 70];
+        truth[3] = 1;
+        let shifted = BitLine::from_bits(&truth[..60]).shifted_right(10);
+        truth.rotate_right(10);
+        let allocated = BitLine::from_bits(&truth);
+        assert_ne!(shifted.data.len(), allocated.data.len());
+        assert_eq!(allocated, shifted);
+        assert_ne!(BitLine::new(65), BitLine::new(64));
     }
 
     #[test]
@@ -211,12 +766,162 @@ mod tests {
         assert_eq!(Some(7), bitline.end());
     }
 
+    #[test]
+    fn test_runs() {
+        let mut bits = vec![0; 200];
+        bits[3..70].fill(1);
+        bits[128] = 1;
+        bits[190..].fill(1);
+        let bitline = BitLine::from_bits(&bits);
+        assert_eq!(vec![(3, 70), (128, 129), (190, 200)], bitline.runs().collect::<Vec<_>>());
+        assert_eq!(Some(128), bitline.next_one(70));
+        assert_eq!(None, bitline.next_one(200));
+        assert_eq!(200, bitline.next_zero(190));
+    }
+
+    #[test]
+    fn test_scaled_up() {
+        let bitline = BitLine::from_bits(&[1, 0, 1, 1]);
+        assert_eq!(vec![1, 1, 1, 0, 0, 0, 1, 1, 1, 1, 1, 1], bitline.scaled_up(3).to_bits());
+    }
+
     #[test]
     fn test_width() {
         let bitline = BitLine::from_bits(&vec![0, 0, 1, 0, 1, 0, 1, 1, 0, 0, 0]);
         assert_eq!(6, bitline.width());
     }
 
+    #[test]
+    fn test_sub_line() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..200).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let bitline = BitLine::from_bits(&truth);
+        assert_eq!(truth[70..150].to_vec(), bitline.sub_line(70, 80).to_bits());
+        // bits past the end are read as 0
+        let mut padded_truth = truth[190..].to_vec();
+        padded_truth.resize(20, 0);
+        assert_eq!(padded_truth, bitline.sub_line(190, 20).to_bits());
+    }
+
+    #[test]
+    fn test_reversed() {
+        let mut rng = rand::thread_rng();
+        let mut truth = (0..100).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let bitline = BitLine::from_bits(&truth);
+        truth.reverse();
+        assert_eq!(truth, bitline.reversed().to_bits());
+    }
+
+    #[test]
+    fn test_repeated() {
+        let mut rng = rand::thread_rng();
+        let pattern = (0..13).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let truth = pattern.iter().cycle().take(150).copied().collect::<Vec<_>>();
+        assert_eq!(truth, BitLine::from_bits(&pattern).repeated(150).to_bits());
+    }
+
+    #[test]
+    fn test_padded() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..100).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let mut padded_truth = vec![0; 70];
+        padded_truth.extend_from_slice(&truth);
+        padded_truth.resize(padded_truth.len() + 3, 0);
+        assert_eq!(padded_truth, BitLine::from_bits(&truth).padded(70, 3).to_bits());
+    }
+
+    #[test]
+    fn test_to_bits_unallocated() {
+        // padding and shifting grow the line past its allocated chunks
+        let mut rng = rand::thread_rng();
+        let mut bits = (0..64).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        bits[61..].fill(0);
+        let bitline = BitLine::from_bits(&bits);
+        let mut padded_truth = bits.clone();
+        padded_truth.resize(164, 0);
+        let mut shifted_truth = vec![0; 3];
+        shifted_truth.extend_from_slice(&bits);
+        for (line, truth) in [(bitline.padded(0, 100), padded_truth), (bitline.shifted_right(3), shifted_truth)] {
+            assert_eq!(truth, line.to_bits());
+            assert_eq!(line, BitLine::from_bits(&line.to_bits()));
+            let display = truth.iter().map(|&bit| if bit == 1 { BIT_1 } else { BIT_0 }).collect::<String>();
+            assert_eq!(display, line.get_display(1));
+        }
+        assert_eq!(110, BitLine::from_bits(&[1; 10]).padded(0, 100).to_bits().len());
+    }
+
+    #[test]
+    fn test_fill_clear() {
+        let mut bitline = BitLine::new(70);
+        bitline.fill();
+        assert_eq!(vec![1; 70], bitline.to_bits());
+        assert_eq!(Some(69), bitline.end());
+        bitline.clear();
+        assert_eq!(None, bitline.start());
+    }
+
+    #[test]
+    fn test_count_ones_range() {
+        let mut bits = vec![0; 200];
+        bits[3..70].fill(1);
+        bits[190..].fill(1);
+        let bitline = BitLine::from_bits(&bits);
+        assert_eq!(67, bitline.count_ones_range(0, 100));
+        assert_eq!(15, bitline.count_ones_range(60, 195));
+        assert_eq!(10, bitline.count_ones_range(190, 300));
+    }
+
+    #[test]
+    fn test_fill_clear_range() {
+        let mut bitline = BitLine::new(150);
+        bitline.fill_range(60, 140);
+        let mut truth = vec![0; 150];
+        truth[60..140].fill(1);
+        assert_eq!(truth, bitline.to_bits());
+        bitline.clear_range(62, 63);
+        truth[62] = 0;
+        assert_eq!(truth, bitline.to_bits());
+        // the range is clipped to the line
+        bitline.fill_range(145, 200);
+        truth[145..].fill(1);
+        assert_eq!(truth, bitline.to_bits());
+        bitline.toggle_range(100, 150);
+        truth[100..].iter_mut().for_each(|bit| *bit = 1 - *bit);
+        assert_eq!(truth, bitline.to_bits());
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut rng = rand::thread_rng();
+        let truth = (0..100).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let mut bitline = BitLine::from_bits(&truth);
+        bitline.invert();
+        assert_eq!(truth.iter().map(|bit| 1 - bit).collect::<Vec<_>>(), bitline.to_bits());
+        assert!(bitline.end().unwrap() < 100);
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let mut rng = rand::thread_rng();
+        let a = (0..100).map(|_| rng.gen_range(0..=1)).collect::<Vec<_>>();
+        let b = (0..80).map(|_| rng.gen_range(0..=1)).collect::<Vec<u8>>();
+        let truth = (0..100).map(|i| ((a[i] == 1) != (b.get(i) == Some(&1))) as u8).collect::<Vec<_>>();
+        let res = BitLine::from_bits(&a).zip_with(&BitLine::from_bits(&b), |a, b| a != b);
+        assert_eq!(truth, res.to_bits());
+        let not_a = BitLine::from_bits(&a).map(|a| !a);
+        assert_eq!(a.iter().map(|bit| 1 - bit).collect::<Vec<_>>(), not_a.to_bits());
+    }
+
+    #[test]
+    fn test_combine_at() {
+        let mut bitline = BitLine::new(100);
+        let source = BitLine::from_bits(&[1; 100]);
+        bitline.combine_at(&source, 1, |a, b| a | b);
+        let mut truth = vec![0; 100];
+        truth[64..].fill(1);
+        assert_eq!(truth, bitline.to_bits());
+    }
+
     #[test]
     fn test_display() {
         let mut rng = rand::thread_rng();
@@ -0,0 +1,75 @@
+use crate::{bitline::BitLine, error::RowLengthError, BinaryRaster};
+
+/// Builds a raster row by row, for when its height isn't known up front
+#[derive(Debug, Clone, Default)]
+pub struct RasterBuilder {
+    lines: Vec<BitLine>,
+}
+
+impl RasterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(height: usize) -> Self {
+        Self { lines: Vec::with_capacity(height) }
+    }
+
+    /// The amount of rows pushed so far
+    pub fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Appends a row of pixels, failing if it doesn't have the same length as the first row
+    pub fn push_row(&mut self, row: &[u8]) -> Result<(), RowLengthError> {
+        self.check_len(row.len())?;
+        self.lines.push(BitLine::from_bits(row));
+        Ok(())
+    }
+
+    /// Appends a row of bits, failing if it doesn't have the same length as the first row
+    pub fn push_row_bits(&mut self, row: &BitLine) -> Result<(), RowLengthError> {
+        self.check_len(row.len())?;
+        self.lines.push(row.clone());
+        Ok(())
+    }
+
+    fn check_len(&self, found: usize) -> Result<(), RowLengthError> {
+        match self.lines.first() {
+            Some(first) if first.len() != found => Err(RowLengthError {
+                row: self.lines.len(),
+                expected: first.len(),
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Turns the pushed rows into a raster
+    pub fn build(self) -> BinaryRaster {
+        BinaryRaster(self.lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RasterBuilder;
+    use crate::{BinaryRaster, BitLine, RowLengthError};
+
+    #[test]
+    fn test_push_rows() {
+        let mut builder = RasterBuilder::new();
+        builder.push_row(&[0, 1, 0]).unwrap();
+        builder.push_row_bits(&BitLine::from_bits(&[1, 1, 1])).unwrap();
+        assert_eq!(
+            Err(RowLengthError { row: 2, expected: 3, found: 2 }),
+            builder.push_row(&[1, 1])
+        );
+        let raster = BinaryRaster::from_raster(&[
+            0, 1, 0,
+            1, 1, 1,
+        ], 3);
+        assert_eq!(2, builder.height());
+        assert_eq!(raster, builder.build());
+    }
+}
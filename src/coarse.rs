@@ -0,0 +1,67 @@
+use crate::{bitline::BitLine, BinaryRaster};
+
+impl BinaryRaster {
+    /// A raster where the bit (i, y) is set if the chunk i of the line y of self (the bits i * usize::BITS to (i + 1) * usize::BITS)
+    /// has any bit with a value of 1, usable as a broad phase for collision_check_at_with_word_mask
+    pub fn word_mask(&self) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().map(|bitline| BitLine::from_fn(bitline.chunk_width(), |i| bitline.chunk(i) != 0)).collect(),
+        )
+    }
+
+    /// False if other placed at pos can't collide with the raster word_mask was computed from,
+    /// checking the chunks covered by the extent of each line of other without shifting it
+    fn may_collide_coarse(word_mask: &BinaryRaster, other: &BinaryRaster, pos: (isize, isize)) -> bool {
+        other.0.iter().enumerate().any(|(line_i, bitline)| {
+            let y = line_i as isize + pos.1;
+            let (Some(start), Some(end)) = (bitline.start(), bitline.end()) else {
+                return false;
+            };
+            let (start, end) = (pos.0 + start as isize, pos.0 + end as isize);
+            if y < 0 || y >= word_mask.height() as isize || end < 0 {
+                return false;
+            }
+            let (first_chunk, last_chunk) = (BitLine::chunked(start.max(0) as usize).0, BitLine::chunked(end as usize).0);
+            word_mask.0[y as usize].count_ones_range(first_chunk, last_chunk + 1) > 0
+        })
+    }
+
+    /// Same as collision_check_at, rejecting the positions where other only covers empty chunks of self
+    /// before doing the bit level check, word_mask must be self.word_mask()
+    /// This is much faster than collision_check_at on mostly empty rasters
+    pub fn collision_check_at_with_word_mask(&self, word_mask: &BinaryRaster, other: &BinaryRaster, pos: (isize, isize)) -> bool {
+        BinaryRaster::may_collide_coarse(word_mask, other, pos) && self.collision_check_at(other, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_word_mask() {
+        let mut raster = BinaryRaster::new(200, 3);
+        raster.set(3, 0, true);
+        raster.set(130, 0, true);
+        raster.set(64, 2, true);
+        let truth = BinaryRaster::from_raster(&[
+            1, 0, 1, 0,
+            0, 0, 0, 0,
+            0, 1, 0, 0,
+        ], 4);
+        assert_eq!(truth, raster.word_mask());
+    }
+
+    #[test]
+    fn test_collision_check_at_with_word_mask() {
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(500, 40, |_, _| rng.gen_bool(0.002));
+        let word_mask = raster.word_mask();
+        let sprite = BinaryRaster::from_fn(70, 6, |_, _| rng.gen_bool(0.5));
+        for _ in 0..500 {
+            let pos = (rng.gen_range(-80..510), rng.gen_range(-8..42));
+            assert_eq!(raster.collision_check_at(&sprite, pos), raster.collision_check_at_with_word_mask(&word_mask, &sprite, pos));
+        }
+    }
+}
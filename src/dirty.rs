@@ -0,0 +1,154 @@
+use crate::BinaryRaster;
+
+/// A raster recording the rectangles (x, y, w, h) its mutations may have changed,
+/// so a copy of it (such as a texture) can be updated without being rebuilt entirely
+/// The rectangles are clipped to the raster and may overlap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirtyTracker {
+    raster: BinaryRaster,
+    dirty: Vec<(usize, usize, usize, usize)>,
+}
+
+impl DirtyTracker {
+    /// Starts tracking the mutations of raster, with no dirty region
+    pub fn new(raster: BinaryRaster) -> Self {
+        DirtyTracker { raster, dirty: Vec::new() }
+    }
+
+    pub fn raster(&self) -> &BinaryRaster {
+        &self.raster
+    }
+
+    /// Stops tracking the mutations, dropping the dirty regions
+    pub fn into_inner(self) -> BinaryRaster {
+        self.raster
+    }
+
+    /// The rectangles changed since the last call, in the order of the mutations
+    pub fn take_dirty_regions(&mut self) -> Vec<(usize, usize, usize, usize)> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Records the rectangle from (x0, y0) to (x1, y1) excluded, clipped to the raster, if it's not empty
+    fn mark(&mut self, (x0, y0): (isize, isize), (x1, y1): (isize, isize)) {
+        let (x0, y0) = (x0.max(0) as usize, y0.max(0) as usize);
+        let x1 = x1.clamp(0, self.raster.width() as isize) as usize;
+        let y1 = y1.clamp(0, self.raster.height() as isize) as usize;
+        if x0 < x1 && y0 < y1 {
+            self.dirty.push((x0, y0, x1 - x0, y1 - y0));
+        }
+    }
+
+    /// Records the bounding box of source placed at pos
+    fn mark_placed(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        if let Some((x0, y0, x1, y1)) = source.bounding_box() {
+            let (x0, y0) = (pos.0 + x0 as isize, pos.1 + y0 as isize);
+            self.mark((x0, y0), (pos.0 + x1 as isize + 1, pos.1 + y1 as isize + 1));
+        }
+    }
+
+    /// Records the w x h rectangle with its top left corner at (x, y)
+    fn mark_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        let x1 = x.saturating_add(w).min(self.raster.width());
+        let y1 = y.saturating_add(h).min(self.raster.height());
+        if x < x1 && y < y1 {
+            self.dirty.push((x, y, x1 - x, y1 - y));
+        }
+    }
+
+    /// Same as BinaryRaster::set, only recording the bit if its value changes
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        if x < self.raster.width() && y < self.raster.height() && self.raster.get(x, y) != value {
+            self.raster.set(x, y, value);
+            self.mark_rect(x, y, 1, 1);
+        }
+    }
+
+    pub fn add_from(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        self.raster.add_from(source, pos);
+        self.mark_placed(source, pos);
+    }
+
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked(&mut self, source: &BinaryRaster, pos: (isize, isize)) -> Result<(), ()> {
+        self.raster.add_from_checked(source, pos)?;
+        self.mark_placed(source, pos);
+        Ok(())
+    }
+
+    pub fn remove_from(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        self.raster.remove_from(source, pos);
+        self.mark_placed(source, pos);
+    }
+
+    pub fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        self.raster.fill_rect(x, y, w, h);
+        self.mark_rect(x, y, w, h);
+    }
+
+    pub fn clear_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        self.raster.clear_rect(x, y, w, h);
+        self.mark_rect(x, y, w, h);
+    }
+
+    pub fn toggle_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        self.raster.toggle_rect(x, y, w, h);
+        self.mark_rect(x, y, w, h);
+    }
+
+    pub fn clear(&mut self) {
+        self.raster.clear();
+        self.mark_rect(0, 0, self.raster.width(), self.raster.height());
+    }
+
+    pub fn fill(&mut self) {
+        self.raster.fill();
+        self.mark_rect(0, 0, self.raster.width(), self.raster.height());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::DirtyTracker;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_dirty_regions() {
+        let mut tracker = DirtyTracker::new(BinaryRaster::new(10, 6));
+        let sprite: BinaryRaster = "
+            ...
+            .##
+            .#.
+        ".parse().unwrap();
+        tracker.add_from(&sprite, (-2, 0));
+        tracker.set(9, 5, true);
+        tracker.set(9, 5, true);
+        tracker.set(20, 5, true);
+        assert_eq!(Err(()), tracker.add_from_checked(&sprite, (-1, -1)));
+        tracker.fill_rect(8, 4, 5, 5);
+        assert_eq!(vec![(0, 1, 1, 2), (9, 5, 1, 1), (8, 4, 2, 2)], tracker.take_dirty_regions());
+        assert!(tracker.take_dirty_regions().is_empty());
+        tracker.remove_from(&sprite, (0, 0));
+        tracker.clear_rect(20, 0, 3, 3);
+        tracker.add_from(&BinaryRaster::new(3, 3), (0, 0));
+        assert_eq!(vec![(1, 1, 2, 2)], tracker.take_dirty_regions());
+    }
+
+    #[test]
+    fn test_dirty_regions_cover_changes() {
+        let mut rng = rand::thread_rng();
+        let mut tracker = DirtyTracker::new(BinaryRaster::from_fn(150, 30, |_, _| rng.gen_bool(0.1)));
+        for _ in 0..20 {
+            let mut copy = tracker.raster().clone();
+            let sprite = BinaryRaster::from_fn(rng.gen_range(1..40), rng.gen_range(1..8), |_, _| rng.gen_bool(0.3));
+            tracker.add_from(&sprite, (rng.gen_range(-40..155), rng.gen_range(-8..35)));
+            tracker.toggle_rect(rng.gen_range(0..160), rng.gen_range(0..35), rng.gen_range(0..30), rng.gen_range(0..10));
+            for (x, y, w, h) in tracker.take_dirty_regions() {
+                copy.clear_rect(x, y, w, h);
+                copy.add_from(&tracker.raster().crop(x, y, w, h), (x as isize, y as isize));
+            }
+            assert_eq!(tracker.raster(), &copy);
+        }
+    }
+}
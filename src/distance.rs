@@ -0,0 +1,204 @@
+use crate::BinaryRaster;
+
+/// The chamfer cost of a horizontal or vertical step, distances being measured in thirds of a pixel
+pub(crate) const ORTHOGONAL_COST: u16 = 3;
+/// The chamfer cost of a diagonal step, approximating 3√2
+const DIAGONAL_COST: u16 = 4;
+
+impl BinaryRaster {
+    /// The approximate distance from every bit to the nearest bit with a value of 1, row by row,
+    /// in thirds of a pixel using the 3-4 chamfer metric, u16::MAX if the raster is empty
+    /// Computed in 2 passes over the raster, the error compared to the euclidean distance is at most around 8%
+    pub fn distance_transform(&self) -> Vec<u16> {
+        let (width, height) = (self.width(), self.height());
+        let mut distances: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| if self.get(x, y) { 0 } else { u16::MAX })
+            .collect();
+        let forward = [(-1, 0, ORTHOGONAL_COST), (-1, -1, DIAGONAL_COST), (0, -1, ORTHOGONAL_COST), (1, -1, DIAGONAL_COST)];
+        let backward = forward.map(|(dx, dy, cost)| (-dx, -dy, cost));
+        let positions = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+        for ((x, y), neighbours) in positions.clone().map(|p| (p, &forward)).chain(positions.rev().map(|p| (p, &backward))) {
+            for &(dx, dy, cost) in neighbours {
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if nx >= 0 && ny >= 0 && (nx as usize) < width && (ny as usize) < height {
+                    let through_neighbour = distances[ny as usize * width + nx as usize].saturating_add(cost);
+                    let distance = &mut distances[y * width + x];
+                    *distance = (*distance).min(through_neighbour);
+                }
+            }
+        }
+        distances
+    }
+
+    /// The approximate signed distance from the center of every bit to the edge of the regions of bits with a value of 1, row by row,
+    /// in pixels, positive outside of the regions and negative inside, the edge being halfway between 2 bits of different values
+    /// Based on distance_transform, infinite if there is no bit of the other value
+    pub fn signed_distance_field(&self) -> Vec<f32> {
+        let to_pixels = |distance: u16| if distance == u16::MAX { f32::INFINITY } else { distance as f32 / ORTHOGONAL_COST as f32 };
+        self.distance_transform().into_iter()
+            .zip(self.inverted().distance_transform())
+            .map(|(outside, inside)| if outside == 0 { 0.5 - to_pixels(inside) } else { to_pixels(outside) - 0.5 })
+            .collect()
+    }
+
+    /// The bit with a value of 0 closest to from by euclidean distance, None if there are none
+    /// Ties are broken by taking the top most then left most bit
+    pub fn nearest_zero(&self, from: (usize, usize)) -> Option<(usize, usize)> {
+        self.nearest_bit((from.0 as isize, from.1 as isize), false, usize::MAX).map(|(_, y, x)| (x, y))
+    }
+
+    /// The (squared distance, y, x) of the bit with the given value closest to from, ignoring the bits
+    /// with a squared distance of bound or more, None if there are none
+    /// The lines are searched going away from from, each one in a few word operations, until they are too far to be closer
+    fn nearest_bit(&self, from: (isize, isize), value: bool, bound: usize) -> Option<(usize, usize, usize)> {
+        // the tuple comparison only lets through the bits with a squared distance below bound
+        let mut best = (bound, 0, 0);
+        let last_dy = from.1.max(self.height() as isize - 1 - from.1);
+        for dy in 0..=last_dy.max(0) {
+            if (dy * dy) as usize > best.0 {
+                break;
+            }
+            let rows = [from.1 - dy, from.1 + dy];
+            for y in rows[..if dy == 0 { 1 } else { 2 }].iter().filter_map(|&y| usize::try_from(y).ok()) {
+                let Some(bitline) = self.0.get(y) else {
+                    continue;
+                };
+                // the nearest bits at or left of from and at or right of from
+                let left = usize::try_from(from.0).ok().map(|x| x.min(bitline.bits.saturating_sub(1)));
+                let right = usize::try_from(from.0).unwrap_or(0);
+                let (left, right) = if value {
+                    (left.and_then(|x| bitline.prev_one(x)), bitline.next_one(right))
+                } else {
+                    (left.and_then(|x| bitline.prev_zero(x)), Some(bitline.next_zero(right)).filter(|&x| x < bitline.bits))
+                };
+                for x in [left, right].into_iter().flatten() {
+                    let candidate = ((x as isize - from.0).pow(2) as usize + (dy * dy) as usize, y, x);
+                    best = best.min(candidate);
+                }
+            }
+        }
+        (best.0 < bound).then_some(best)
+    }
+
+    /// The smallest euclidean distance between the centers of a bit with a value of 1 of the raster
+    /// and a bit with a value of 1 of other placed at pos, 0 if they collide, None if either has no such bit
+    pub fn min_distance_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> Option<f32> {
+        if self.collision_check_at(other, pos) {
+            return Some(0.);
+        }
+        let mut best = usize::MAX;
+        for (line_i, bitline) in other.0.iter().enumerate() {
+            for (start, end) in bitline.runs() {
+                for x in start..end {
+                    let from = (pos.0 + x as isize, pos.1 + line_i as isize);
+                    if let Some((distance, _, _)) = self.nearest_bit(from, true, best) {
+                        best = distance;
+                    }
+                }
+            }
+        }
+        (best < usize::MAX).then(|| (best as f32).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_distance_transform() {
+        let raster: BinaryRaster = "
+            ......
+            ..#...
+            ......
+            ......
+        ".parse().unwrap();
+        assert_eq!(vec![
+            7, 4, 3, 4, 7, 10,
+            6, 3, 0, 3, 6, 9,
+            7, 4, 3, 4, 7, 10,
+            8, 7, 6, 7, 8, 11,
+        ], raster.distance_transform());
+        assert_eq!(vec![u16::MAX; 4], BinaryRaster::new(2, 2).distance_transform());
+    }
+
+    #[test]
+    fn test_nearest_zero() {
+        let raster: BinaryRaster = "
+            ....##
+            .#####
+            ######
+        ".parse().unwrap();
+        assert_eq!(Some((0, 0)), raster.nearest_zero((0, 0)));
+        assert_eq!(Some((1, 0)), raster.nearest_zero((1, 1)));
+        assert_eq!(Some((3, 0)), raster.nearest_zero((5, 1)));
+        assert_eq!(Some((2, 0)), raster.nearest_zero((2, 2)));
+        let mut full = BinaryRaster::new(3, 3);
+        full.fill();
+        assert_eq!(None, full.nearest_zero((1, 1)));
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 40, |_, _| rng.gen_bool(0.97));
+        for _ in 0..50 {
+            let from = (rng.gen_range(0..160), rng.gen_range(0..45));
+            let truth = (0..40).flat_map(|y| (0..150).map(move |x| (x, y)))
+                .filter(|&(x, y)| !raster.get(x, y))
+                .min_by_key(|&(x, y)| (x.abs_diff(from.0).pow(2) + y.abs_diff(from.1).pow(2), y, x));
+            assert_eq!(truth, raster.nearest_zero(from));
+        }
+    }
+
+    #[test]
+    fn test_min_distance_at() {
+        let raster: BinaryRaster = "
+            #....
+            .....
+            ....#
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            #.
+        ".parse().unwrap();
+        assert_eq!(Some(0.), raster.min_distance_at(&sprite, (0, 0)));
+        assert_eq!(Some(1.), raster.min_distance_at(&sprite, (3, 1)));
+        assert_eq!(Some(5f32.sqrt()), raster.min_distance_at(&sprite, (-2, 2)));
+        assert_eq!(None, raster.min_distance_at(&BinaryRaster::new(2, 2), (0, 0)));
+        assert_eq!(None, BinaryRaster::new(5, 3).min_distance_at(&sprite, (0, 0)));
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(90, 30, |_, _| rng.gen_bool(0.005));
+        let sprite = BinaryRaster::from_fn(8, 5, |_, _| rng.gen_bool(0.3));
+        let ones = |raster: &BinaryRaster| (0..raster.height())
+            .flat_map(|y| (0..raster.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| raster.get(x, y))
+            .collect::<Vec<_>>();
+        for _ in 0..50 {
+            let pos = (rng.gen_range(-12..95), rng.gen_range(-8..35));
+            let truth = ones(&raster).iter()
+                .flat_map(|&(x, y)| ones(&sprite).into_iter().map(move |(sx, sy)| {
+                    (x as isize - sx as isize - pos.0).pow(2) + (y as isize - sy as isize - pos.1).pow(2)
+                }))
+                .min()
+                .map(|distance| (distance as f32).sqrt());
+            assert_eq!(truth, raster.min_distance_at(&sprite, pos));
+        }
+    }
+
+    #[test]
+    fn test_signed_distance_field() {
+        let raster: BinaryRaster = "
+            .....
+            .###.
+            .###.
+            .###.
+        ".parse().unwrap();
+        let d = 4. / 3. - 0.5;
+        assert_eq!(vec![
+            d, 0.5, 0.5, 0.5, d,
+            0.5, -0.5, -0.5, -0.5, 0.5,
+            0.5, -0.5, -1.5, -0.5, 0.5,
+            0.5, -0.5, -1.5, -0.5, 0.5,
+        ], raster.signed_distance_field());
+        assert_eq!(vec![f32::INFINITY; 4], BinaryRaster::new(2, 2).signed_distance_field());
+    }
+}
@@ -0,0 +1,756 @@
+use std::f64::consts::TAU;
+use crate::BinaryRaster;
+
+/// The maximum ratio between the length of a miter join and half the width of the stroke, longer miters are beveled instead
+const MITER_LIMIT: f64 = 4.;
+
+/// How the ends of a stroked path are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cap {
+    /// The stroke stops at the end points
+    Butt,
+    /// The stroke is extended by half its width past the end points
+    Square,
+    /// The stroke is extended by a half disc around the end points
+    Round,
+}
+
+/// How the corners of a stroked path are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Join {
+    /// The outer edges of the stroke are extended until they meet, beveled if they meet too far away
+    Miter,
+    /// The outer edges of the stroke are connected by a straight line
+    Bevel,
+    /// The corner is rounded by a disc around it
+    Round,
+}
+
+impl BinaryRaster {
+    /// Sets the pixel at (x, y) to 1, does nothing if it's outside of the raster
+    fn plot(&mut self, x: i32, y: i32) {
+        if x >= 0 && y >= 0 {
+            self.set(x as usize, y as usize, true);
+        }
+    }
+
+    /// Sets the 1 pixel wide outline of the w x h rectangle with its top left corner at (x, y) to 1, clipped to the raster
+    pub fn draw_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        self.fill_rect_outline(x, y, w, h, 1);
+    }
+
+    /// Sets the border pixels wide outline of the w x h rectangle with its top left corner at (x, y) to 1, clipped to the raster
+    /// The border grows inward, filling the whole rectangle if it's thick enough
+    pub fn fill_rect_outline(&mut self, x: usize, y: usize, w: usize, h: usize, border: usize) {
+        if 2 * border >= w || 2 * border >= h {
+            self.fill_rect(x, y, w, h);
+            return;
+        }
+        self.fill_rect(x, y, w, border);
+        self.fill_rect(x, y + h - border, w, border);
+        self.fill_rect(x, y + border, border, h - 2 * border);
+        self.fill_rect(x + w - border, y + border, border, h - 2 * border);
+    }
+
+    /// Sets the pixels from x0 to x1 (included) of the line y to 1, clipped to the raster
+    fn fill_span(&mut self, y: i32, x0: i32, x1: i32) {
+        if y < 0 || x1 < 0 || x1 < x0 {
+            return;
+        }
+        if let Some(bitline) = self.0.get_mut(y as usize) {
+            bitline.fill_range(x0.max(0) as usize, x1 as usize + 1);
+        }
+    }
+
+    /// Sets every pixel of the line from (x0, y0) to (x1, y1) to 1 using Bresenham's algorithm, ends included
+    /// The parts of the line outside of the raster are ignored
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        for (x, y) in line_points((x0 as isize, y0 as isize), (x1 as isize, y1 as isize)) {
+            self.plot(x as i32, y as i32);
+        }
+    }
+
+    /// Draws lines between successive points, and between the last and the first point if closed
+    pub fn draw_path(&mut self, points: &[(i32, i32)], closed: bool) {
+        for segment in points.windows(2) {
+            self.draw_line(segment[0].0, segment[0].1, segment[1].0, segment[1].1);
+        }
+        if let (true, Some(&first), Some(&last)) = (closed, points.first(), points.last()) {
+            self.draw_line(last.0, last.1, first.0, first.1);
+        }
+    }
+
+    /// Draws the Bézier curve defined by the control points, 3 for a quadratic curve, 4 for a cubic curve
+    /// The curve is approximated by lines, subdividing it until each line is within a quarter of a pixel of the curve
+    pub fn draw_bezier(&mut self, control_points: &[(f32, f32)]) {
+        let Some(&first) = control_points.first() else {
+            return;
+        };
+        let mut points = vec![first];
+        flatten_bezier(control_points, &mut points, 0);
+        let points: Vec<(i32, i32)> = points.iter().map(|p| (p.0.round() as i32, p.1.round() as i32)).collect();
+        self.draw_path(&points, false);
+    }
+
+    /// Sets the pixels at (cx ± x, cy ± y) to 1, or the lines between them if filled
+    fn plot_symmetric(&mut self, cx: i32, cy: i32, x: i32, y: i32, filled: bool) {
+        if filled {
+            self.fill_span(cy - y, cx - x, cx + x);
+            self.fill_span(cy + y, cx - x, cx + x);
+        } else {
+            for (px, py) in [(cx - x, cy - y), (cx + x, cy - y), (cx - x, cy + y), (cx + x, cy + y)] {
+                self.plot(px, py);
+            }
+        }
+    }
+
+    /// Draws the circle of center (cx, cy) and radius r using the midpoint circle algorithm, filling it if filled
+    /// The parts of the circle outside of the raster are ignored
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, r: i32, filled: bool) {
+        for (x, y) in circle_octant(r) {
+            self.plot_symmetric(cx, cy, x, y, filled);
+            self.plot_symmetric(cx, cy, y, x, filled);
+        }
+    }
+
+    /// Draws the arc of the circle of center (cx, cy) and radius r going clockwise from start_angle to end_angle,
+    /// in radians with 0 pointing right, filling the pie slice between the arc and the center if filled
+    /// The parts of the arc outside of the raster are ignored
+    pub fn draw_arc(&mut self, cx: i32, cy: i32, r: i32, start_angle: f64, end_angle: f64, filled: bool) {
+        if r < 0 {
+            return;
+        }
+        let sweep = end_angle - start_angle;
+        let in_arc = |dx: i32, dy: i32| {
+            sweep >= TAU
+                || (dx == 0 && dy == 0)
+                || ((dy as f64).atan2(dx as f64) - start_angle).rem_euclid(TAU) <= sweep.rem_euclid(TAU)
+        };
+        let octant = circle_octant(r);
+        if filled {
+            // the half width of the circle for each distance to its center
+            let mut half_widths = vec![0; octant.len().max(r as usize + 1)];
+            for &(x, y) in &octant {
+                half_widths[y as usize] = half_widths[y as usize].max(x);
+                half_widths[x as usize] = half_widths[x as usize].max(y);
+            }
+            for dy in -r..=r {
+                let half_width = half_widths[dy.unsigned_abs() as usize];
+                for dx in (-half_width..=half_width).filter(|&dx| in_arc(dx, dy)) {
+                    self.plot(cx + dx, cy + dy);
+                }
+            }
+        } else {
+            for (x, y) in octant {
+                for (dx, dy) in [(x, y), (y, x)] {
+                    for (dx, dy) in [(-dx, -dy), (dx, -dy), (-dx, dy), (dx, dy)] {
+                        if in_arc(dx, dy) {
+                            self.plot(cx + dx, cy + dy);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the axis aligned ellipse of center (cx, cy) and radii rx and ry using the midpoint ellipse algorithm, filling it if filled
+    /// The parts of the ellipse outside of the raster are ignored
+    pub fn draw_ellipse(&mut self, cx: i32, cy: i32, rx: i32, ry: i32, filled: bool) {
+        if rx < 0 || ry < 0 {
+            return;
+        }
+        let (rx2, ry2) = ((rx as f64).powi(2), (ry as f64).powi(2));
+        let (mut x, mut y) = (0, ry);
+        let (mut px, mut py) = (0., 2. * rx2 * y as f64);
+        // region where the slope of the curve is less than 1, stepping on x
+        let mut decision = ry2 - rx2 * ry as f64 + rx2 / 4.;
+        while px < py {
+            self.plot_symmetric(cx, cy, x, y, filled);
+            x += 1;
+            px += 2. * ry2;
+            if decision < 0. {
+                decision += ry2 + px;
+            } else {
+                y -= 1;
+                py -= 2. * rx2;
+                decision += ry2 + px - py;
+            }
+        }
+        // region where the slope of the curve is more than 1, stepping on y
+        decision = ry2 * (x as f64 + 0.5).powi(2) + rx2 * (y as f64 - 1.).powi(2) - rx2 * ry2;
+        while y >= 0 {
+            self.plot_symmetric(cx, cy, x, y, filled);
+            y -= 1;
+            py -= 2. * rx2;
+            if decision > 0. {
+                decision += rx2 - py;
+            } else {
+                x += 1;
+                px += 2. * ry2;
+                decision += rx2 - py + px;
+            }
+        }
+    }
+
+    /// Sets every pixel whose center is inside of the polygon to 1 using the even-odd rule, the polygon being closed automatically
+    /// Every line of the polygon is filled a whole span at a time, the parts of the polygon outside of the raster are ignored
+    pub fn fill_polygon(&mut self, points: &[(i32, i32)]) {
+        let points: Vec<(f64, f64)> = points.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+        self.fill_polygon_f64(&points);
+    }
+
+    /// Same as fill_polygon, with sub-pixel coordinates
+    fn fill_polygon_f64(&mut self, points: &[(f64, f64)]) {
+        if points.len() < 3 {
+            return;
+        }
+        let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min).floor().max(0.) as i32;
+        let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max).ceil().min(self.height() as f64) as i32;
+        let mut crossings = Vec::new();
+        for y in min_y..max_y {
+            let scan_y = y as f64 + 0.5;
+            crossings.clear();
+            for (i, &(x0, y0)) in points.iter().enumerate() {
+                let (x1, y1) = points[(i + 1) % points.len()];
+                if (y0 <= scan_y) != (y1 <= scan_y) {
+                    crossings.push(x0 + (scan_y - y0) / (y1 - y0) * (x1 - x0));
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                // the pixels with their center in span[0]..span[1]
+                self.fill_span(y, (span[0] - 0.5).ceil() as i32, (span[1] - 0.5).ceil() as i32 - 1);
+            }
+        }
+    }
+
+    /// Sets every pixel whose center is at a distance of at most radius from center to 1
+    fn fill_disc(&mut self, center: (f64, f64), radius: f64) {
+        let y_start = (center.1 - radius - 0.5).ceil().max(0.) as i32;
+        let y_end = (center.1 + radius - 0.5).floor().min(self.height() as f64) as i32;
+        for y in y_start..=y_end {
+            let dy = y as f64 + 0.5 - center.1;
+            let half_width = (radius * radius - dy * dy).max(0.).sqrt();
+            self.fill_span(y, (center.0 - half_width - 0.5).ceil() as i32, (center.0 + half_width - 0.5).floor() as i32);
+        }
+    }
+
+    /// Sets every pixel whose center is covered by the stroke of the path to 1, as in vector graphics,
+    /// the stroke being width wide with its ends drawn according to cap and its corners according to join
+    /// The parts of the stroke outside of the raster are ignored
+    pub fn stroke_path(&mut self, points: &[(f32, f32)], width: f32, cap: Cap, join: Join) {
+        let half_width = width as f64 / 2.;
+        let mut points: Vec<(f64, f64)> = points.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+        points.dedup();
+        let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+            return;
+        };
+        if half_width <= 0. {
+            return;
+        }
+        // the direction and the normal of each segment, the normal being half_width long
+        let segments: Vec<((f64, f64), (f64, f64))> = points.windows(2).map(|segment| {
+            let (dx, dy) = (segment[1].0 - segment[0].0, segment[1].1 - segment[0].1);
+            let length = dx.hypot(dy);
+            let direction = (dx / length, dy / length);
+            (direction, (-direction.1 * half_width, direction.0 * half_width))
+        }).collect();
+        for (i, ((&a, &b), &(direction, normal))) in points.iter().zip(&points[1..]).zip(&segments).enumerate() {
+            let (mut a, mut b) = (a, b);
+            if cap == Cap::Square {
+                if i == 0 {
+                    a = (a.0 - direction.0 * half_width, a.1 - direction.1 * half_width);
+                }
+                if i == segments.len() - 1 {
+                    b = (b.0 + direction.0 * half_width, b.1 + direction.1 * half_width);
+                }
+            }
+            self.fill_polygon_f64(&[
+                (a.0 + normal.0, a.1 + normal.1),
+                (b.0 + normal.0, b.1 + normal.1),
+                (b.0 - normal.0, b.1 - normal.1),
+                (a.0 - normal.0, a.1 - normal.1),
+            ]);
+        }
+        match cap {
+            Cap::Round => {
+                self.fill_disc(first, half_width);
+                self.fill_disc(last, half_width);
+            }
+            // a single point has no direction to extend it along
+            Cap::Square if segments.is_empty() => self.fill_polygon_f64(&[
+                (first.0 - half_width, first.1 - half_width),
+                (first.0 + half_width, first.1 - half_width),
+                (first.0 + half_width, first.1 + half_width),
+                (first.0 - half_width, first.1 + half_width),
+            ]),
+            _ => {}
+        }
+        for (&vertex, pair) in points[1..].iter().zip(segments.windows(2)) {
+            let ((d0, n0), (d1, n1)) = (pair[0], pair[1]);
+            if join == Join::Round {
+                self.fill_disc(vertex, half_width);
+                continue;
+            }
+            // the corner to fill is on the outer side of the turn
+            let side = if d0.0 * d1.1 - d0.1 * d1.0 > 0. { -1. } else { 1. };
+            let (n0, n1) = ((n0.0 * side, n0.1 * side), (n1.0 * side, n1.1 * side));
+            let corner0 = (vertex.0 + n0.0, vertex.1 + n0.1);
+            let corner1 = (vertex.0 + n1.0, vertex.1 + n1.1);
+            let (mx, my) = (n0.0 + n1.0, n0.1 + n1.1);
+            let miter_squared = mx * mx + my * my;
+            // the tip of the miter is at a distance of half_width / cos(turn angle / 2) from the vertex
+            let miter_length = 2. * half_width * half_width / miter_squared.sqrt();
+            if join == Join::Miter && miter_length <= MITER_LIMIT * half_width {
+                let scale = 2. * half_width * half_width / miter_squared;
+                let tip = (vertex.0 + mx * scale, vertex.1 + my * scale);
+                self.fill_polygon_f64(&[vertex, corner0, tip, corner1]);
+            } else {
+                self.fill_polygon_f64(&[vertex, corner0, corner1]);
+            }
+        }
+    }
+
+    /// Creates a width x height raster with the convex hull of the points filled, boundary included
+    /// The parts of the hull outside of the raster are ignored
+    pub fn from_convex_hull(points: &[(i32, i32)], width: usize, height: usize) -> BinaryRaster {
+        let mut raster = BinaryRaster::new(width, height);
+        let hull = convex_hull(points);
+        raster.fill_polygon(&hull);
+        // pixel-center sampling can leave out the edges of the hull, and degenerate hulls have no inside
+        raster.draw_path(&hull, true);
+        raster
+    }
+
+    /// Returns a raster of the same size with the convex hull of the bits with a value of 1 filled,
+    /// the hull going around the corners of the pixels so it covers all of them
+    pub fn convex_hull_mask(&self) -> BinaryRaster {
+        let corners: Vec<(i32, i32)> = self.0.iter().enumerate().flat_map(|(y, bitline)| {
+            let y = y as i32;
+            bitline.runs().flat_map(move |(start, end)| {
+                let (start, end) = (start as i32, end as i32);
+                [(start, y), (end, y), (start, y + 1), (end, y + 1)]
+            })
+        }).collect();
+        let mut mask = BinaryRaster::new(self.width(), self.height());
+        mask.fill_polygon(&convex_hull(&corners));
+        mask
+    }
+
+    /// Sets every pixel at a distance of at most thickness/2 from the segment from p0 to p1 to 1, drawing a capsule
+    /// The parts of the capsule outside of the raster are ignored
+    pub fn draw_line_thick(&mut self, p0: (i32, i32), p1: (i32, i32), thickness: f32) {
+        let radius = thickness / 2.;
+        let margin = radius.ceil() as i32;
+        let (a, b) = ((p0.0 as f32, p0.1 as f32), (p1.0 as f32, p1.1 as f32));
+        // the bounds saturate for huge thicknesses and are clamped to the raster, the span of a line being found inside of it
+        let min_x = p0.0.min(p1.0).saturating_sub(margin).max(0);
+        let max_x = p0.0.max(p1.0).saturating_add(margin).min(self.width() as i32 - 1);
+        let y_end = p0.1.max(p1.1).saturating_add(margin).min(self.height() as i32 - 1);
+        for y in p0.1.min(p1.1).saturating_sub(margin).max(0)..=y_end {
+            let inside = |x: &i32| distance_to_segment((*x as f32, y as f32), a, b) <= radius;
+            // a capsule is convex so the pixels inside of it form a single span on each line
+            if let Some(start) = (min_x..=max_x).find(inside) {
+                let end = (start..=max_x).rev().find(inside).unwrap_or(start);
+                self.fill_span(y, start, end);
+            }
+        }
+    }
+}
+
+/// The points of the line from p0 to p1 using Bresenham's algorithm, in order, ends included
+pub(crate) fn line_points(p0: (isize, isize), p1: (isize, isize)) -> impl Iterator<Item = (isize, isize)> {
+    let (dx, dy) = ((p1.0 - p0.0).abs(), -(p1.1 - p0.1).abs());
+    let (step_x, step_y) = (if p0.0 < p1.0 { 1 } else { -1 }, if p0.1 < p1.1 { 1 } else { -1 });
+    let mut point = Some(p0);
+    let mut error = dx + dy;
+    std::iter::from_fn(move || {
+        let (x, y) = point?;
+        point = if (x, y) == p1 {
+            None
+        } else {
+            let double_error = 2 * error;
+            let (mut next_x, mut next_y) = (x, y);
+            if double_error >= dy {
+                error += dy;
+                next_x += step_x;
+            }
+            if double_error <= dx {
+                error += dx;
+                next_y += step_y;
+            }
+            Some((next_x, next_y))
+        };
+        Some((x, y))
+    })
+}
+
+/// The points (x, y) of the circle of radius r centered on (0, 0) with 0 <= y <= x, using the midpoint circle algorithm
+fn circle_octant(r: i32) -> Vec<(i32, i32)> {
+    let mut points = Vec::new();
+    let (mut x, mut y) = (r, 0);
+    let mut decision = 1 - r;
+    while x >= y {
+        points.push((x, y));
+        y += 1;
+        if decision < 0 {
+            decision += 2 * y + 1;
+        } else {
+            x -= 1;
+            decision += 2 * (y - x) + 1;
+        }
+    }
+    points
+}
+
+/// The vertices of the convex hull of the points in order, without collinear points
+fn convex_hull(points: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // Andrew's monotone chain, each half of the hull only turning one way
+    let half_hull = |points: &mut dyn Iterator<Item = &(i32, i32)>| {
+        let mut half: Vec<(i32, i32)> = Vec::new();
+        for &p in points {
+            while let [.., o, a] = half[..] {
+                let cross = (a.0 as i64 - o.0 as i64) * (p.1 as i64 - o.1 as i64)
+                    - (a.1 as i64 - o.1 as i64) * (p.0 as i64 - o.0 as i64);
+                if cross > 0 {
+                    break;
+                }
+                half.pop();
+            }
+            half.push(p);
+        }
+        // the last point is the first point of the other half
+        half.pop();
+        half
+    };
+    let mut hull = half_hull(&mut points.iter());
+    hull.extend(half_hull(&mut points.iter().rev()));
+    hull
+}
+
+/// The maximum distance between the flattened curve and the actual curve, in pixels
+const FLATNESS_TOLERANCE: f32 = 0.25;
+/// Bounds the amount of subdivisions for degenerate curves
+const MAX_SUBDIVISIONS: u32 = 16;
+
+/// Appends the points of a polyline approximating the Bézier curve to out, except the first control point
+fn flatten_bezier(control_points: &[(f32, f32)], out: &mut Vec<(f32, f32)>, depth: u32) {
+    let (first, last) = (control_points[0], control_points[control_points.len() - 1]);
+    let is_flat = control_points.iter().all(|&p| distance_to_segment(p, first, last) <= FLATNESS_TOLERANCE);
+    if is_flat || depth >= MAX_SUBDIVISIONS {
+        out.push(last);
+        return;
+    }
+    // de Casteljau subdivision at t = 0.5
+    let mut left = Vec::with_capacity(control_points.len());
+    let mut right = Vec::with_capacity(control_points.len());
+    let mut level = control_points.to_vec();
+    while !level.is_empty() {
+        left.push(level[0]);
+        right.push(level[level.len() - 1]);
+        level = level.windows(2).map(|w| ((w[0].0 + w[1].0) / 2., (w[0].1 + w[1].1) / 2.)).collect();
+    }
+    right.reverse();
+    flatten_bezier(&left, out, depth + 1);
+    flatten_bezier(&right, out, depth + 1);
+}
+
+/// The distance between p and the segment from a to b
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (ab_x, ab_y) = (b.0 - a.0, b.1 - a.1);
+    let length_squared = ab_x * ab_x + ab_y * ab_y;
+    let t = if length_squared == 0. {
+        0.
+    } else {
+        (((p.0 - a.0) * ab_x + (p.1 - a.1) * ab_y) / length_squared).clamp(0., 1.)
+    };
+    let (dx, dy) = (p.0 - a.0 - t * ab_x, p.1 - a.1 - t * ab_y);
+    (dx * dx + dy * dy).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI, TAU};
+    use rand::Rng;
+    use crate::{BinaryRaster, Cap, Join};
+
+    #[test]
+    fn test_draw_line() {
+        let mut raster = BinaryRaster::new(5, 4);
+        raster.draw_line(0, 0, 4, 2);
+        raster.draw_line(4, -2, 1, 4);
+        let truth: BinaryRaster = "
+            #..#.
+            .##..
+            ..###
+            .#...
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_draw_path() {
+        let points = [(0, 0), (4, 0), (4, 3), (0, 3)];
+        let mut open = BinaryRaster::new(5, 4);
+        open.draw_path(&points, false);
+        let truth: BinaryRaster = "
+            #####
+            ....#
+            ....#
+            #####
+        ".parse().unwrap();
+        assert_eq!(truth, open);
+        let mut closed = BinaryRaster::new(5, 4);
+        closed.draw_path(&points, true);
+        let mut truth = BinaryRaster::new(5, 4);
+        truth.draw_rect(0, 0, 5, 4);
+        assert_eq!(truth, closed);
+    }
+
+    #[test]
+    fn test_draw_bezier() {
+        // a degenerate curve is a line
+        let mut raster = BinaryRaster::new(6, 3);
+        raster.draw_bezier(&[(0., 1.), (2., 1.), (3., 1.), (5., 1.)]);
+        let mut truth = BinaryRaster::new(6, 3);
+        truth.draw_line(0, 1, 5, 1);
+        assert_eq!(truth, raster);
+        let mut raster = BinaryRaster::new(9, 5);
+        raster.draw_bezier(&[(0., 4.), (4., -4.), (8., 4.)]);
+        let truth: BinaryRaster = "
+            ...###...
+            ..#...#..
+            .#.....#.
+            .#......#
+            #.......#
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_draw_rect() {
+        let mut raster = BinaryRaster::new(9, 6);
+        raster.draw_rect(0, 0, 4, 3);
+        raster.fill_rect_outline(3, 1, 6, 5, 2);
+        let truth: BinaryRaster = "
+            ####.....
+            #..######
+            #########
+            ...##..##
+            ...######
+            ...######
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_draw_circle() {
+        let mut raster = BinaryRaster::new(5, 5);
+        raster.draw_circle(2, 2, 2, false);
+        let truth: BinaryRaster = "
+            .###.
+            #...#
+            #...#
+            #...#
+            .###.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        raster.draw_circle(2, 2, 2, true);
+        let truth: BinaryRaster = "
+            .###.
+            #####
+            #####
+            #####
+            .###.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_draw_arc() {
+        for filled in [false, true] {
+            let mut circle = BinaryRaster::new(11, 11);
+            circle.draw_circle(5, 5, 5, filled);
+            let mut raster = BinaryRaster::new(11, 11);
+            raster.draw_arc(5, 5, 5, 1., 1. + TAU, filled);
+            assert_eq!(circle, raster);
+            // the bottom right quarter of the circle
+            let mut raster = BinaryRaster::new(11, 11);
+            raster.draw_arc(5, 5, 5, 0., FRAC_PI_2, filled);
+            circle.clear_rect(0, 0, 11, 5);
+            circle.clear_rect(0, 0, 5, 11);
+            assert_eq!(circle, raster);
+        }
+        // going clockwise from the bottom left to the top left
+        let mut raster = BinaryRaster::new(5, 5);
+        raster.draw_arc(2, 2, 2, PI * 0.7, PI * 1.3, true);
+        let truth: BinaryRaster = "
+            .....
+            ##...
+            ###..
+            ##...
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        // a negative radius draws nothing, like draw_circle
+        for r in [-1, -2] {
+            for filled in [false, true] {
+                let mut raster = BinaryRaster::new(5, 5);
+                raster.draw_arc(2, 2, r, 0., PI, filled);
+                assert_eq!(BinaryRaster::new(5, 5), raster);
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_ellipse() {
+        let mut raster = BinaryRaster::new(7, 3);
+        raster.draw_ellipse(3, 1, 3, 1, false);
+        let truth: BinaryRaster = "
+            .#####.
+            #.....#
+            .#####.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        raster.draw_ellipse(3, 1, 3, 1, true);
+        let truth: BinaryRaster = "
+            .#####.
+            #######
+            .#####.
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        let mut raster = BinaryRaster::new(6, 5);
+        // a U shape
+        raster.fill_polygon(&[(0, 0), (2, 0), (2, 3), (4, 3), (4, 0), (6, 0), (6, 5), (0, 5)]);
+        let truth: BinaryRaster = "
+            ##..##
+            ##..##
+            ##..##
+            ######
+            ######
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        let mut raster = BinaryRaster::new(5, 5);
+        raster.fill_polygon(&[(0, 0), (5, 0), (0, 5)]);
+        let truth: BinaryRaster = "
+            ####.
+            ###..
+            ##...
+            #....
+            .....
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+    }
+
+    #[test]
+    fn test_from_convex_hull() {
+        let points = [(1, 0), (4, 3), (1, 3), (2, 2), (0, 4), (4, 0), (3, 1)];
+        let truth: BinaryRaster = "
+            .####.
+            .####.
+            #####.
+            ##.##.
+            ###...
+        ".parse().unwrap();
+        assert_eq!(truth, BinaryRaster::from_convex_hull(&points, 6, 5));
+        // degenerate hulls
+        assert_eq!(BinaryRaster::from_rows(&[&[0, 1, 1, 1]]).unwrap(), BinaryRaster::from_convex_hull(&[(1, 0), (3, 0), (2, 0)], 4, 1));
+        assert_eq!(BinaryRaster::new(2, 2), BinaryRaster::from_convex_hull(&[], 2, 2));
+    }
+
+    #[test]
+    fn test_stroke_path() {
+        let mut raster = BinaryRaster::new(8, 5);
+        raster.stroke_path(&[(1., 2.5), (6., 2.5)], 3., Cap::Butt, Join::Miter);
+        let mut truth = BinaryRaster::new(8, 5);
+        truth.fill_rect(1, 1, 5, 3);
+        assert_eq!(truth, raster);
+        raster.stroke_path(&[(1., 2.5), (6., 2.5)], 3., Cap::Square, Join::Miter);
+        truth.fill_rect(0, 1, 7, 3);
+        assert_eq!(truth, raster);
+        let path = [(0., 2.5), (8.5, 2.5), (8.5, 7.)];
+        for (join, truth) in [(Join::Miter, "
+            ###########.
+            ###########.
+            ###########.
+            ###########.
+            ###########.
+            ......#####.
+            ......#####.
+        "), (Join::Bevel, "
+            #########...
+            ##########..
+            ###########.
+            ###########.
+            ###########.
+            ......#####.
+            ......#####.
+        "), (Join::Round, "
+            ##########..
+            ###########.
+            ###########.
+            ###########.
+            ###########.
+            ......#####.
+            ......#####.
+        ")] {
+            let mut raster = BinaryRaster::new(12, 7);
+            raster.stroke_path(&path, 5., Cap::Butt, join);
+            assert_eq!(truth.parse::<BinaryRaster>().unwrap(), raster);
+        }
+    }
+
+    #[test]
+    fn test_convex_hull_mask() {
+        let raster: BinaryRaster = "
+            ......
+            ..#...
+            ......
+            #...#.
+            ......
+        ".parse().unwrap();
+        let hull: BinaryRaster = "
+            ......
+            .##...
+            ####..
+            #####.
+            ......
+        ".parse().unwrap();
+        assert_eq!(hull, raster.convex_hull_mask());
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(40, 30, |_, _| rng.gen_bool(0.01));
+        assert_eq!(0, raster.difference(&raster.convex_hull_mask()).count_ones());
+        assert_eq!(BinaryRaster::new(3, 3), BinaryRaster::new(3, 3).convex_hull_mask());
+    }
+
+    #[test]
+    fn test_draw_line_thick() {
+        let mut raster = BinaryRaster::new(8, 5);
+        raster.draw_line_thick((1, 2), (6, 2), 2.5);
+        let truth: BinaryRaster = "
+            ........
+            .######.
+            ########
+            .######.
+            ........
+        ".parse().unwrap();
+        assert_eq!(truth, raster);
+        // the margin of a huge thickness doesn't overflow the coordinates
+        let mut raster = BinaryRaster::new(8, 5);
+        raster.draw_line_thick((1, 2), (6, 2), f32::MAX);
+        assert_eq!(40, raster.count_ones());
+        let mut raster = BinaryRaster::new(8, 5);
+        raster.draw_line_thick((i32::MIN, 2), (i32::MAX, 2), 2.5);
+        assert_eq!(8 * 3, raster.count_ones());
+    }
+}
@@ -0,0 +1,81 @@
+use core::fmt::{self, Display};
+
+/// Returned when building a raster from rows that don't all have the same length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowLengthError {
+    /// Index of the first row with an unexpected length
+    pub row: usize,
+    /// Length of the first row
+    pub expected: usize,
+    /// Length of that row
+    pub found: usize,
+}
+
+impl Display for RowLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {} has a length of {}, expected {}", self.row, self.found, self.expected)
+    }
+}
+
+impl std::error::Error for RowLengthError {}
+
+/// Checks that every row length is the same, returning that length (0 if there are no rows)
+pub(crate) fn check_row_lengths(lengths: impl IntoIterator<Item = usize>) -> Result<usize, RowLengthError> {
+    let mut lengths = lengths.into_iter();
+    let Some(expected) = lengths.next() else {
+        return Ok(0);
+    };
+    for (i, found) in lengths.enumerate() {
+        if found != expected {
+            return Err(RowLengthError { row: i + 1, expected, found });
+        }
+    }
+    Ok(expected)
+}
+
+
+/// Returned when parsing a raster from ASCII art fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseRasterError {
+    /// The text at the given row and byte offset in that row is neither the on nor the off token
+    UnknownToken { row: usize, offset: usize },
+    /// The rows don't all have the same amount of bits
+    RowLength(RowLengthError),
+}
+
+impl Display for ParseRasterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseRasterError::UnknownToken { row, offset } => write!(f, "unknown token at row {}, offset {}", row, offset),
+            ParseRasterError::RowLength(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ParseRasterError {}
+
+impl From<RowLengthError> for ParseRasterError {
+    fn from(err: RowLengthError) -> Self {
+        ParseRasterError::RowLength(err)
+    }
+}
+
+/// Returned when a raster can't be added because it collides with bits that are already set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionError {
+    /// The raster collides with the bits of the raster it's added to
+    Target,
+    /// The raster collides with the item at the given index of the same batch
+    Item(usize),
+}
+
+impl Display for CollisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollisionError::Target => write!(f, "collides with the target raster"),
+            CollisionError::Item(index) => write!(f, "collides with item {}", index),
+        }
+    }
+}
+
+impl std::error::Error for CollisionError {}
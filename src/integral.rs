@@ -0,0 +1,68 @@
+use crate::BinaryRaster;
+
+/// A summed-area table of a raster, counting the bits with a value of 1 in any rectangle in constant time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummedAreaTable {
+    /// The amount of bits with a value of 1 above and left of (x, y) at index y * (width + 1) + x, for x <= width and y <= height
+    sums: Vec<usize>,
+    width: usize,
+    height: usize,
+}
+
+impl SummedAreaTable {
+    /// The amount of bits with a value of 1 in the w x h rectangle with its top left corner at (x, y), clipped to the raster
+    pub fn count_ones_in(&self, x: usize, y: usize, w: usize, h: usize) -> usize {
+        let (x0, y0) = (x.min(self.width), y.min(self.height));
+        let (x1, y1) = (x.saturating_add(w).min(self.width), y.saturating_add(h).min(self.height));
+        self.sum(x1, y1) + self.sum(x0, y0) - self.sum(x0, y1) - self.sum(x1, y0)
+    }
+
+    /// The amount of bits with a value of 1 above and left of (x, y)
+    fn sum(&self, x: usize, y: usize) -> usize {
+        self.sums[y * (self.width + 1) + x]
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl BinaryRaster {
+    /// The summed-area table of the raster
+    pub fn integral(&self) -> SummedAreaTable {
+        let (width, height) = (self.width(), self.height());
+        let mut sums = vec![0; (width + 1) * (height + 1)];
+        for (y, bitline) in self.0.iter().enumerate() {
+            let mut row_sum = 0;
+            for x in 0..width {
+                row_sum += bitline.get(x) as usize;
+                sums[(y + 1) * (width + 1) + x + 1] = sums[y * (width + 1) + x + 1] + row_sum;
+            }
+        }
+        SummedAreaTable { sums, width, height }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_count_ones_in() {
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(130, 40, |_, _| rng.gen_bool(0.3));
+        let integral = raster.integral();
+        assert_eq!((130, 40), (integral.width(), integral.height()));
+        assert_eq!(raster.count_ones(), integral.count_ones_in(0, 0, 130, 40));
+        assert_eq!(0, integral.count_ones_in(130, 0, 10, 10));
+        for _ in 0..200 {
+            let (x, y, w, h) = (rng.gen_range(0..140), rng.gen_range(0..45), rng.gen_range(0..80), rng.gen_range(0..30));
+            assert_eq!(raster.crop(x, y, w, h).count_ones(), integral.count_ones_in(x, y, w, h));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::BinaryRaster;
+
+/// Named rasters of the same size stacked on top of each other, each layer being a collision group
+/// The layer i is the group 1 << i, so a set of layers is a bitmask of groups and collisions
+/// can be checked against any subset of the layers, e.g. text checked against images and margins but not other text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayeredRaster {
+    layers: Vec<(String, BinaryRaster)>,
+    width: usize,
+    height: usize,
+}
+
+impl LayeredRaster {
+    /// The maximum amount of layers, one per bit of a group mask
+    pub const MAX_LAYERS: usize = u64::BITS as usize;
+    /// The groups mask of every layer
+    pub const ALL_GROUPS: u64 = u64::MAX;
+
+    /// A layered raster with no layer, the layers will be width x height
+    pub fn new(width: usize, height: usize) -> Self {
+        LayeredRaster { layers: Vec::new(), width, height }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Adds an empty layer on top of the others and returns its group,
+    /// None if there's already a layer with this name or MAX_LAYERS layers
+    pub fn add_layer(&mut self, name: &str) -> Option<u64> {
+        if self.layers.len() >= LayeredRaster::MAX_LAYERS || self.group(name).is_some() {
+            return None;
+        }
+        self.layers.push((name.to_string(), BinaryRaster::new(self.width, self.height)));
+        Some(1 << (self.layers.len() - 1))
+    }
+
+    /// The group of the layer with this name, None if there's none
+    pub fn group(&self, name: &str) -> Option<u64> {
+        self.index(name).map(|i| 1 << i)
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|(layer_name, _)| layer_name == name)
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&BinaryRaster> {
+        self.index(name).map(|i| &self.layers[i].1)
+    }
+
+    /// The layer with this name, None if there's none
+    /// The layer must stay width x height, merged and the collision checks assume every layer has the same size
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut BinaryRaster> {
+        self.index(name).map(|i| &mut self.layers[i].1)
+    }
+
+    /// The names of the layers from the bottom one to the top one
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The layers in groups, from the bottom one to the top one
+    fn layers_in(&self, groups: u64) -> impl Iterator<Item = &BinaryRaster> {
+        self.layers.iter().enumerate().filter(move |(i, _)| groups >> i & 1 == 1).map(|(_, (_, layer))| layer)
+    }
+
+    /// Checks if other placed at pos overlaps any bit of the layers in groups
+    pub fn collision_check_at(&self, other: &BinaryRaster, pos: (isize, isize), groups: u64) -> bool {
+        self.layers_in(groups).any(|layer| layer.collision_check_at(other, pos))
+    }
+
+    /// Adds source at pos to the layer with this name if it doesn't overlap any bit of the layers in groups,
+    /// which may or may not include the layer itself
+    /// None if there's no layer with this name
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked(&mut self, name: &str, source: &BinaryRaster, pos: (isize, isize), groups: u64) -> Option<Result<(), ()>> {
+        let i = self.index(name)?;
+        if self.collision_check_at(source, pos, groups) {
+            return Some(Err(()));
+        }
+        self.layers[i].1.add_from(source, pos);
+        Some(Ok(()))
+    }
+
+    /// The union of the layers in groups
+    pub fn merged(&self, groups: u64) -> BinaryRaster {
+        debug_assert!(
+            self.layers.iter().all(|(_, layer)| (layer.width(), layer.height()) == (self.width, self.height)),
+            "every layer must be {}x{}", self.width, self.height,
+        );
+        let mut merged = BinaryRaster::new(self.width, self.height);
+        for layer in self.layers_in(groups) {
+            merged.add_from(layer, (0, 0));
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayeredRaster;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_layers() {
+        let mut layered = LayeredRaster::new(6, 3);
+        let margins = layered.add_layer("margins").unwrap();
+        let images = layered.add_layer("images").unwrap();
+        let text = layered.add_layer("text").unwrap();
+        assert_eq!((1, 2, 4), (margins, images, text));
+        assert_eq!(None, layered.add_layer("text"));
+        assert_eq!(Some(images), layered.group("images"));
+        assert_eq!(vec!["margins", "images", "text"], layered.names().collect::<Vec<_>>());
+        layered.layer_mut("margins").unwrap().fill_rect(0, 0, 1, 3);
+        layered.layer_mut("images").unwrap().fill_rect(4, 1, 2, 2);
+        let word: BinaryRaster = "##".parse().unwrap();
+        assert_eq!(Some(Err(())), layered.add_from_checked("text", &word, (0, 0), margins | images | text));
+        assert_eq!(Some(Ok(())), layered.add_from_checked("text", &word, (1, 0), margins | images | text));
+        assert_eq!(Some(Err(())), layered.add_from_checked("text", &word, (2, 0), margins | images | text));
+        assert_eq!(Some(Ok(())), layered.add_from_checked("text", &word, (2, 0), margins | images));
+        assert_eq!(Some(Err(())), layered.add_from_checked("text", &word, (3, 1), margins | images));
+        assert_eq!(None, layered.add_from_checked("captions", &word, (1, 2), margins));
+        assert!(!layered.collision_check_at(&word, (3, 1), margins | text));
+        let merged: BinaryRaster = "
+            ####..
+            #...##
+            #...##
+        ".parse().unwrap();
+        assert_eq!(merged, layered.merged(LayeredRaster::ALL_GROUPS));
+        assert_eq!(layered.layer("images"), Some(&layered.merged(images)));
+        let mut full = LayeredRaster::new(1, 1);
+        for i in 0..LayeredRaster::MAX_LAYERS {
+            assert_eq!(Some(1 << i), full.add_layer(&i.to_string()));
+        }
+        assert_eq!(None, full.add_layer("extra"));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_resized_layer() {
+        let mut layered = LayeredRaster::new(6, 3);
+        layered.add_layer("images").unwrap();
+        layered.layer_mut("images").unwrap().pad(0, 2, 0, 0);
+        layered.merged(LayeredRaster::ALL_GROUPS);
+    }
+}
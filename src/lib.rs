@@ -1,3 +1,46 @@
+mod affine;
+mod anchor;
 mod bitline;
 mod binary_raster;
-pub use binary_raster::BinaryRaster;
\ No newline at end of file
+mod builder;
+mod coarse;
+mod dirty;
+mod distance;
+mod draw;
+mod error;
+mod integral;
+mod layers;
+mod metrics;
+mod morphology;
+mod ops;
+mod packer;
+mod placement;
+mod preshifted;
+mod pyramid;
+mod quadtree;
+mod regions;
+mod snapshot;
+mod transaction;
+mod transform;
+pub use affine::Affine2;
+pub use anchor::Anchor;
+pub use bitline::{BitLine, BIT_0, BIT_1};
+pub use binary_raster::BinaryRaster;
+pub use builder::RasterBuilder;
+pub use dirty::DirtyTracker;
+pub use draw::{Cap, Join};
+pub use error::{CollisionError, ParseRasterError, RowLengthError};
+pub use integral::SummedAreaTable;
+pub use layers::LayeredRaster;
+pub use metrics::RasterDiff;
+pub use morphology::StructuringElement;
+pub use packer::{Packer, Placement, PlacementId};
+#[cfg(feature = "rand")]
+pub use placement::RandomPlacement;
+pub use placement::{DensityPlacement, PlacementStrategy, ScanlinePlacement, SpiralPlacement};
+pub use preshifted::PreshiftedSprite;
+pub use pyramid::RasterPyramid;
+pub use quadtree::QuadTreeIndex;
+pub use snapshot::{Snapshot, UndoStack};
+pub use transaction::Transaction;
+pub use transform::{DownscaleMode, TranslateMode};
\ No newline at end of file
@@ -0,0 +1,348 @@
+use crate::{bitline::{BitLine, EMPTY_LINE}, BinaryRaster};
+
+/// A rectangle (x0, y0, x1, y1) of changed pixels, bounds included
+type Region = (usize, usize, usize, usize);
+/// A run of changed pixels (start, end) on a line, bounds included
+type Run = (usize, usize);
+
+/// The pixels that changed between two rasters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterDiff {
+    /// The pixels that differ, covering the size of both rasters
+    pub xor: BinaryRaster,
+    /// Rectangles (x0, y0, x1, y1), bounds included, covering every changed pixel, in reading order of their top left corner,
+    /// one for each group of runs of changed pixels touching each other (diagonals included) across consecutive lines
+    pub regions: Vec<(usize, usize, usize, usize)>,
+}
+
+impl BinaryRaster {
+    /// Pairs of chunks of self and other at the same position, chunks outside of either raster are read as 0
+    fn chunk_pairs<'a>(&'a self, other: &'a BinaryRaster) -> impl Iterator<Item = (usize, usize)> + 'a {
+        (0..self.height().max(other.height()))
+            .flat_map(move |line_i| {
+                let bitline = self.0.get(line_i).unwrap_or(&EMPTY_LINE);
+                let other_bitline = other.0.get(line_i).unwrap_or(&EMPTY_LINE);
+                bitline.chunk_pairs(other_bitline)
+            })
+    }
+
+    /// The Jaccard similarity |self ∩ other| / |self ∪ other| of the two rasters, with both aligned on their top left corner
+    /// Two empty rasters have a similarity of 1
+    pub fn jaccard(&self, other: &BinaryRaster) -> f64 {
+        let (intersection, union) = self.chunk_pairs(other)
+            .fold((0, 0), |(intersection, union), (a, b)| {
+                (intersection + (a & b).count_ones(), union + (a | b).count_ones())
+            });
+        if union == 0 {
+            return 1.;
+        }
+        intersection as f64 / union as f64
+    }
+
+    /// The pixels that differ between self and other with both aligned on their top left corner,
+    /// along with the rectangles covering them
+    pub fn diff(&self, other: &BinaryRaster) -> RasterDiff {
+        let width = self.width().max(other.width());
+        let xor = BinaryRaster(
+            (0..self.height().max(other.height())).map(|line_i| {
+                let mut bitline = BitLine::new(width);
+                bitline.combine_with(self.0.get(line_i).unwrap_or(&EMPTY_LINE), |_, a| a);
+                bitline.combine_with(other.0.get(line_i).unwrap_or(&EMPTY_LINE), |a, b| a ^ b);
+                bitline
+            }).collect(),
+        );
+        // the regions reaching the previous line along with their runs on it (bounds included), which can still grow,
+        // and the ones that can't
+        let mut open: Vec<(Region, Vec<Run>)> = Vec::new();
+        let mut regions: Vec<Region> = Vec::new();
+        let touches = |(start, end): Run, runs: &[Run]| {
+            runs.iter().any(|&(other_start, other_end)| other_start <= end + 1 && start <= other_end + 1)
+        };
+        for (line_i, bitline) in xor.0.iter().enumerate() {
+            // the regions reaching this line along with the runs of the previous line they absorbed and their runs on this line
+            let mut current: Vec<(Region, Vec<Run>, Vec<Run>)> = Vec::new();
+            for (start, end) in bitline.runs() {
+                let run = (start, end - 1);
+                let (mut region, mut above, mut runs) = ((run.0, line_i, run.1, line_i), Vec::new(), vec![run]);
+                let mut absorb = |(x0, y0, x1, _): Region| {
+                    region = (region.0.min(x0), region.1.min(y0), region.2.max(x1), line_i);
+                };
+                // runs on the same line never touch, so a run only joins the regions it touches on the previous line
+                open.retain(|(other, last_runs)| {
+                    let joined = touches(run, last_runs);
+                    if joined {
+                        absorb(*other);
+                        above.extend_from_slice(last_runs);
+                    }
+                    !joined
+                });
+                current.retain(|(other, other_above, other_runs)| {
+                    let joined = touches(run, other_above);
+                    if joined {
+                        absorb(*other);
+                        above.extend_from_slice(other_above);
+                        runs.extend_from_slice(other_runs);
+                    }
+                    !joined
+                });
+                current.push((region, above, runs));
+            }
+            regions.extend(open.drain(..).map(|(region, _)| region));
+            open = current.into_iter().map(|(region, _, runs)| (region, runs)).collect();
+        }
+        regions.extend(open.into_iter().map(|(region, _)| region));
+        regions.sort_by_key(|&(x0, y0, _, _)| (y0, x0));
+        RasterDiff { xor, regions }
+    }
+
+    /// The amount of bits with a value of 1 that have a 4-neighbour with a value of 0, bits on the edge of the raster included
+    pub fn perimeter(&self) -> usize {
+        self.outline().count_ones()
+    }
+
+    /// The amount of 8-connected regions minus the amount of holes (4-connected regions of 0 inside of them)
+    /// Counts the 2x2 patterns of bits (Gray's bit quads) a whole chunk at a time, the raster being surrounded by 0
+    pub fn euler_number(&self) -> isize {
+        let chunks = BitLine::chunks_to_fit(self.width() + 1);
+        let shifted: Vec<BitLine> = self.0.iter().map(|bitline| bitline.shifted_right(1)).collect();
+        let (mut one_set, mut three_set, mut diagonal) = (0, 0, 0);
+        for line_i in 0..=self.height() {
+            // the line above and the line below the quads, with a copy shifted right to get the left column of the quads
+            let top = line_i.checked_sub(1).map(|above| (&self.0[above], &shifted[above])).unwrap_or((&EMPTY_LINE, &EMPTY_LINE));
+            let bottom = (self.0.get(line_i).unwrap_or(&EMPTY_LINE), shifted.get(line_i).unwrap_or(&EMPTY_LINE));
+            for i in 0..chunks {
+                let (a, b, c, d) = (top.1.chunk(i), top.0.chunk(i), bottom.1.chunk(i), bottom.0.chunk(i));
+                let (top_both, top_one, bottom_both, bottom_one) = (a & b, a ^ b, c & d, c ^ d);
+                let three = (top_both & bottom_one) | (bottom_both & top_one);
+                one_set += ((top_one ^ bottom_one) & !three).count_ones() as isize;
+                three_set += three.count_ones() as isize;
+                diagonal += ((a & d & !b & !c) | (b & c & !a & !d)).count_ones() as isize;
+            }
+        }
+        (one_set - three_set - 2 * diagonal) / 4
+    }
+
+    /// The raw moments of the bits with a value of 1, moments[p][q] being the sum of x^p * y^q over them, for p and q up to order
+    /// Every line is summed over its runs of 1, skipping empty chunks
+    pub fn moments(&self, order: usize) -> Vec<Vec<f64>> {
+        let mut moments = vec![vec![0.; order + 1]; order + 1];
+        let mut line_sums = vec![0.; order + 1];
+        for (y, bitline) in self.0.iter().enumerate() {
+            line_sums.fill(0.);
+            for (start, end) in bitline.runs() {
+                for x in start..end {
+                    let mut x_power = 1.;
+                    for sum in line_sums.iter_mut() {
+                        *sum += x_power;
+                        x_power *= x as f64;
+                    }
+                }
+            }
+            for (row, &sum) in moments.iter_mut().zip(line_sums.iter()) {
+                let mut y_power = 1.;
+                for moment in row.iter_mut() {
+                    *moment += sum * y_power;
+                    y_power *= y as f64;
+                }
+            }
+        }
+        moments
+    }
+
+    /// The mean position (x, y) of the bits with a value of 1, None if the raster is empty
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        let moments = self.moments(1);
+        if moments[0][0] == 0. {
+            return None;
+        }
+        Some((moments[1][0] / moments[0][0], moments[0][1] / moments[0][0]))
+    }
+
+    /// The amount of pixels that differ between self and other, with both aligned on their top left corner
+    /// Pixels outside of a raster are read as 0, so the set pixels of the bigger raster that are outside of the smaller one count as different
+    pub fn hamming_distance(&self, other: &BinaryRaster) -> usize {
+        self.chunk_pairs(other).map(|(a, b)| (a ^ b).count_ones() as usize).sum()
+    }
+
+    /// The amount of bits with a value of 1 in each block_w x block_h block of the raster, row by row,
+    /// with width.div_ceil(block_w) blocks per row, the blocks on the right and bottom edges may be smaller
+    /// A block size of 0 gives an empty heatmap
+    pub fn heatmap(&self, block_w: usize, block_h: usize) -> Vec<u32> {
+        if block_w == 0 || block_h == 0 {
+            return Vec::new();
+        }
+        let width = self.width();
+        self.0.chunks(block_h).flat_map(|lines| {
+            (0..width.div_ceil(block_w)).map(move |block_x| {
+                let (start, end) = (block_x * block_w, ((block_x + 1) * block_w).min(width));
+                lines.iter().map(|bitline| bitline.count_ones_range(start, end) as u32).sum::<u32>()
+            })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::{BinaryRaster, RasterDiff};
+
+    #[test]
+    fn test_perimeter() {
+        let raster: BinaryRaster = "
+            .....
+            .###.
+            .###.
+            .###.
+            ....#
+        ".parse().unwrap();
+        assert_eq!(9, raster.perimeter());
+    }
+
+    #[test]
+    fn test_euler_number() {
+        let raster: BinaryRaster = "
+            ####..#
+            #..#...
+            ####..#
+            ......#
+            ###...#
+            #.#..#.
+            ###.#..
+        ".parse().unwrap();
+        // 4 regions with 2 holes
+        assert_eq!(2, raster.euler_number());
+        assert_eq!(0, BinaryRaster::new(3, 3).euler_number());
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 30, |_, _| rng.gen_bool(0.3));
+        let (regions, with_holes) = (raster.contours().len() as isize, raster.contours_with_holes().len() as isize);
+        assert_eq!(regions - (with_holes - regions), raster.euler_number());
+    }
+
+    #[test]
+    fn test_moments() {
+        let raster: BinaryRaster = "
+            .##.
+            ...#
+        ".parse().unwrap();
+        assert_eq!(vec![
+            vec![3., 1., 1.],
+            vec![6., 3., 3.],
+            vec![14., 9., 9.],
+        ], raster.moments(2));
+        assert_eq!(Some((2., 1. / 3.)), raster.centroid());
+        assert_eq!(None, BinaryRaster::new(2, 2).centroid());
+    }
+
+    #[test]
+    fn test_jaccard() {
+        let a: BinaryRaster = "
+            ##..
+            #...
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            .#..
+            ##..
+            #...
+        ".parse().unwrap();
+        assert_eq!(2. / 5., a.jaccard(&b));
+        assert_eq!(1., a.jaccard(&a));
+        assert_eq!(1., BinaryRaster::new(3, 3).jaccard(&BinaryRaster::new(2, 2)));
+    }
+
+    #[test]
+    fn test_diff() {
+        let a: BinaryRaster = "
+            ##..
+            #...
+            ....
+            .#..
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            .#...
+            ##...
+            .....
+            .#..#
+            ..#..
+        ".parse().unwrap();
+        let xor = "
+            #....
+            .#...
+            .....
+            ....#
+            ..#..
+        ".parse().unwrap();
+        assert_eq!(RasterDiff { xor, regions: vec![(0, 0, 1, 1), (4, 3, 4, 3), (2, 4, 2, 4)] }, a.diff(&b));
+        // far apart changes on adjacent lines aren't merged in one wide region
+        let a = BinaryRaster::new(5000, 10);
+        let mut b = a.clone();
+        b.set(0, 5, true);
+        b.set(4000, 6, true);
+        b.set(4001, 7, true);
+        assert_eq!(vec![(0, 5, 0, 5), (4000, 6, 4001, 7)], a.diff(&b).regions);
+        // runs joined by a run of the next line are merged
+        let c: BinaryRaster = "
+            #...#
+            #####
+            ..#..
+        ".parse().unwrap();
+        assert_eq!(vec![(0, 0, 4, 2)], c.diff(&BinaryRaster::new(5, 3)).regions);
+        // a run only joins a region if it touches its runs on the previous line, not just its bounding box
+        let d: BinaryRaster = "
+            #########.
+            #.........
+            ......#...
+        ".parse().unwrap();
+        assert_eq!(vec![(0, 0, 8, 1), (6, 2, 6, 2)], d.diff(&BinaryRaster::new(10, 3)).regions);
+        let mut rng = rand::thread_rng();
+        let a = BinaryRaster::from_fn(150, 30, |_, _| rng.gen_bool(0.02));
+        let b = BinaryRaster::from_fn(150, 30, |_, _| rng.gen_bool(0.02));
+        let diff = a.diff(&b);
+        let mut covered = BinaryRaster::new(150, 30);
+        for &(x0, y0, x1, y1) in &diff.regions {
+            covered.fill_rect(x0, y0, x1 - x0 + 1, y1 - y0 + 1);
+        }
+        assert_eq!(BinaryRaster::new(150, 30), diff.xor.difference(&covered));
+        // each region is the bounding box of an 8-connected component of the changed pixels
+        let mut truth: Vec<_> = diff.xor.components().into_iter()
+            .map(|(component, (x, y))| (x, y, x + component.width() - 1, y + component.height() - 1))
+            .collect();
+        let mut regions = diff.regions.clone();
+        truth.sort();
+        regions.sort();
+        assert_eq!(truth, regions);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        let a: BinaryRaster = "
+            ##..
+            #...
+        ".parse().unwrap();
+        let b: BinaryRaster = "
+            .#..
+            ##..
+            #...
+        ".parse().unwrap();
+        assert_eq!(3, a.hamming_distance(&b));
+        assert_eq!(0, a.hamming_distance(&a));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let raster: BinaryRaster = "
+            ##...
+            #..##
+            ....#
+        ".parse().unwrap();
+        assert_eq!(vec![3, 1, 1, 0, 0, 1], raster.heatmap(2, 2));
+        assert_eq!(vec![6], raster.heatmap(5, 3));
+        assert_eq!(vec![6], raster.heatmap(10, 10));
+        assert_eq!(Vec::<u32>::new(), raster.heatmap(0, 2));
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 40, |_, _| rng.gen_bool(0.3));
+        let heatmap = raster.heatmap(16, 7);
+        assert_eq!(10 * 6, heatmap.len());
+        assert_eq!(raster.count_ones(), heatmap.iter().sum::<u32>() as usize);
+        assert_eq!(raster.crop(48, 14, 16, 7).count_ones() as u32, heatmap[2 * 10 + 3]);
+    }
+}
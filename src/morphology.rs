@@ -0,0 +1,367 @@
+use crate::{bitline::{BitLine, EMPTY_LINE}, distance::ORTHOGONAL_COST, BinaryRaster};
+
+/// The shape of the neighbourhood used by morphological operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuringElement {
+    /// Every pixel at most k pixels away horizontally and vertically
+    Square,
+    /// Every pixel at a euclidean distance of at most k
+    Disc,
+}
+
+impl StructuringElement {
+    /// The horizontal reach of the element of radius k on the line dy lines away from its center
+    fn half_width(self, k: usize, dy: usize) -> usize {
+        match self {
+            StructuringElement::Square => k,
+            StructuringElement::Disc => (k * k - dy * dy).isqrt(),
+        }
+    }
+}
+
+/// Whether a bit with the given neighbourhood is removed by the first or second sub-iteration of Zhang–Suen
+fn zhang_suen_table(second: bool) -> [bool; 256] {
+    std::array::from_fn(|code| {
+        let p = |i: usize| code >> (i - 2) & 1 == 1;
+        let neighbours = code.count_ones();
+        // the amount of 0 to 1 transitions going around the neighbourhood
+        let transitions = (2..=9).filter(|&i| !p(i) && p((i - 1) % 8 + 2)).count();
+        let (first_side, second_side) = if second { (p(2) && p(4) && p(8), p(2) && p(6) && p(8)) } else { (p(2) && p(4) && p(6), p(4) && p(6) && p(8)) };
+        (2..=6).contains(&neighbours) && transitions == 1 && !first_side && !second_side
+    })
+}
+
+impl BinaryRaster {
+    /// Returns a copy of the raster where every bit with a bit of value 1 in its neighbourhood of radius k is set to 1
+    /// Lines are dilated with word-level shifts, so the cost grows with log(k) horizontally and with k vertically
+    pub fn dilated(&self, k: usize, element: StructuringElement) -> BinaryRaster {
+        self.combined_with_neighbourhood(k, element, BitLine::dilated, |a, b| a | b)
+    }
+
+    /// Returns a copy of the raster where every bit with a bit of value 0 in its neighbourhood of radius k is set to 0,
+    /// bits outside of the raster being read as 0
+    pub fn eroded(&self, k: usize, element: StructuringElement) -> BinaryRaster {
+        self.combined_with_neighbourhood(k, element, BitLine::eroded, |a, b| a & b)
+    }
+
+    /// Returns only the bits with a value of 1 that have a 4-neighbour with a value of 0, the boundary of every region
+    /// Bits on the edge of the raster are part of the boundary
+    pub fn outline(&self) -> BinaryRaster {
+        self.difference(&self.eroded(1, StructuringElement::Disc))
+    }
+
+    /// Returns a raster where a bit is 1 if the bits around it match the kernels,
+    /// every bit of foreground_kernel with a value of 1 requiring a 1 in self and every bit of background_kernel with a value of 1 requiring a 0
+    /// Each kernel is centered on the bit at (width/2, height/2) of its own, bits outside of self are read as 0
+    pub fn hit_or_miss(&self, foreground_kernel: &BinaryRaster, background_kernel: &BinaryRaster) -> BinaryRaster {
+        let mut res = self.clone();
+        for bitline in res.0.iter_mut() {
+            bitline.fill();
+        }
+        for (kernel, op) in [(foreground_kernel, (|a, b| a & b) as fn(usize, usize) -> usize), (background_kernel, |a, b| a & !b)] {
+            let (center_x, center_y) = ((kernel.width() / 2) as isize, (kernel.height() / 2) as isize);
+            for (ky, kernel_line) in kernel.0.iter().enumerate() {
+                let dy = ky as isize - center_y;
+                for (start, end) in kernel_line.runs() {
+                    for kx in start..end {
+                        let dx = kx as isize - center_x;
+                        for (y, bitline) in res.0.iter_mut().enumerate() {
+                            let source = (y as isize + dy).try_into().ok().and_then(|source_y: usize| self.0.get(source_y)).unwrap_or(&EMPTY_LINE);
+                            // the bit at x of the shifted line is the bit at x + dx of source
+                            let shifted = if dx >= 0 { source.shifted_left(dx as usize) } else { source.shifted_right(dx.unsigned_abs()) };
+                            bitline.combine_with(&shifted, op);
+                        }
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the 1 pixel wide skeleton of the regions of the raster using the Zhang–Suen thinning algorithm
+    /// Only the bits with a value of 1 are visited, skipping over empty words
+    pub fn skeletonized(&self) -> BinaryRaster {
+        let tables = [zhang_suen_table(false), zhang_suen_table(true)];
+        let mut res = self.clone();
+        let mut removed = Vec::new();
+        loop {
+            let mut changed = false;
+            for table in &tables {
+                removed.clear();
+                for (y, bitline) in res.0.iter().enumerate() {
+                    let mut x = 0;
+                    while let Some(one) = bitline.next_one(x) {
+                        if table[res.neighbourhood(one, y) as usize] {
+                            removed.push((one, y));
+                        }
+                        x = one + 1;
+                    }
+                }
+                for &(x, y) in &removed {
+                    res.set(x, y, false);
+                }
+                changed |= !removed.is_empty();
+            }
+            if !changed {
+                return res;
+            }
+        }
+    }
+
+    /// Returns the skeleton of the raster along with the distance from every bit of the skeleton to the nearest bit with a value of 0, row by row,
+    /// in pixels with the approximation of distance_transform, bits outside of the raster being read as 0 and bits outside of the skeleton having a distance of 0
+    pub fn medial_axis(&self) -> (BinaryRaster, Vec<f32>) {
+        let skeleton = self.skeletonized();
+        let padded_width = self.width() + 2;
+        let distances = self.padded(1, 1, 1, 1).inverted().distance_transform();
+        let radii = (0..self.height())
+            .flat_map(|y| (0..self.width()).map(move |x| (x, y)))
+            .map(|(x, y)| if skeleton.get(x, y) { distances[(y + 1) * padded_width + x + 1] as f32 / ORTHOGONAL_COST as f32 } else { 0. })
+            .collect();
+        (skeleton, radii)
+    }
+
+    /// The 8 neighbours of (x, y) clockwise from the top one, as the bits 0 to 7
+    fn neighbourhood(&self, x: usize, y: usize) -> u8 {
+        let (left, up) = (x.wrapping_sub(1), y.wrapping_sub(1));
+        [(x, up), (x + 1, up), (x + 1, y), (x + 1, y + 1), (x, y + 1), (left, y + 1), (left, y), (left, up)]
+            .into_iter()
+            .enumerate()
+            .fold(0, |code, (i, (x, y))| code | ((self.get(x, y) as u8) << i))
+    }
+
+    /// Combines every line with the lines within k lines of it, spread by the half width of the element at that distance,
+    /// using op, lines outside of the raster being read as 0
+    fn combined_with_neighbourhood(
+        &self,
+        k: usize,
+        element: StructuringElement,
+        spread: impl Fn(&BitLine, usize) -> BitLine,
+        op: impl Fn(usize, usize) -> usize + Copy,
+    ) -> BinaryRaster {
+        let mut res = self.clone();
+        let mut spread_lines: Vec<BitLine> = Vec::new();
+        let mut spread_width = None;
+        for dy in 0..=k.min(self.height()) {
+            let half_width = element.half_width(k, dy);
+            if spread_width != Some(half_width) {
+                spread_lines = self.0.iter().map(|bitline| spread(bitline, half_width)).collect();
+                spread_width = Some(half_width);
+            }
+            for (y, bitline) in res.0.iter_mut().enumerate() {
+                for source_y in [y.checked_sub(dy), y.checked_add(dy)] {
+                    let source = source_y.and_then(|source_y| spread_lines.get(source_y)).unwrap_or(&EMPTY_LINE);
+                    bitline.combine_with(source, op);
+                }
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::{BinaryRaster, StructuringElement};
+
+    #[test]
+    fn test_dilated() {
+        let raster: BinaryRaster = "
+            .......
+            .......
+            ...#...
+            .......
+            .......
+            ......#
+        ".parse().unwrap();
+        let square: BinaryRaster = "
+            .#####.
+            .#####.
+            .#####.
+            .######
+            .######
+            ....###
+        ".parse().unwrap();
+        let disc: BinaryRaster = "
+            ...#...
+            ..###..
+            .#####.
+            ..###.#
+            ...#.##
+            ....###
+        ".parse().unwrap();
+        assert_eq!(square, raster.dilated(2, StructuringElement::Square));
+        assert_eq!(disc, raster.dilated(2, StructuringElement::Disc));
+        assert_eq!(raster, raster.dilated(0, StructuringElement::Disc));
+        // the same as checking every neighbour of every bit
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 40, |_, _| rng.gen_bool(0.01));
+        for element in [StructuringElement::Square, StructuringElement::Disc] {
+            let k = 5;
+            let truth = BinaryRaster::from_fn(150, 40, |x, y| {
+                (y.saturating_sub(k)..(y + k + 1).min(40)).any(|ny| {
+                    (x.saturating_sub(k)..(x + k + 1).min(150)).any(|nx| {
+                        let (dx, dy) = (x.abs_diff(nx), y.abs_diff(ny));
+                        raster.get(nx, ny) && (element == StructuringElement::Square || dx * dx + dy * dy <= k * k)
+                    })
+                })
+            });
+            assert_eq!(truth, raster.dilated(k, element));
+        }
+    }
+
+    #[test]
+    fn test_eroded() {
+        let raster: BinaryRaster = "
+            ######.
+            ######.
+            ######.
+            ####...
+            ####...
+        ".parse().unwrap();
+        let square: BinaryRaster = "
+            .......
+            .####..
+            .##....
+            .##....
+            .......
+        ".parse().unwrap();
+        let disc: BinaryRaster = "
+            .......
+            .####..
+            .###...
+            .##....
+            .......
+        ".parse().unwrap();
+        assert_eq!(square, raster.eroded(1, StructuringElement::Square));
+        assert_eq!(disc, raster.eroded(1, StructuringElement::Disc));
+        assert_eq!(BinaryRaster::new(7, 5), raster.eroded(3, StructuringElement::Disc));
+        // erosion is the dual of dilation, inside of the raster
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 40, |_, _| rng.gen_bool(0.95));
+        let mut truth = raster.inverted().dilated(4, StructuringElement::Disc).inverted();
+        truth.clear_rect(0, 0, 150, 4);
+        truth.clear_rect(0, 36, 150, 4);
+        truth.clear_rect(0, 0, 4, 40);
+        truth.clear_rect(146, 0, 4, 40);
+        assert_eq!(truth, raster.eroded(4, StructuringElement::Disc));
+    }
+
+    #[test]
+    fn test_skeletonized() {
+        let raster: BinaryRaster = "
+            ..........
+            .########.
+            .########.
+            .########.
+            ..........
+        ".parse().unwrap();
+        let skeleton: BinaryRaster = "
+            ..........
+            ..........
+            ..#####...
+            ..........
+            ..........
+        ".parse().unwrap();
+        assert_eq!(skeleton, raster.skeletonized());
+        let raster: BinaryRaster = "
+            #######...
+            #######...
+            ..###.....
+            ..###.....
+            ..#####...
+            ..#####...
+        ".parse().unwrap();
+        let skeleton: BinaryRaster = "
+            ..#.#.....
+            ...#......
+            ...#......
+            ...#......
+            ...###....
+            ..........
+        ".parse().unwrap();
+        assert_eq!(skeleton, raster.skeletonized());
+        assert_eq!(skeleton, skeleton.skeletonized());
+    }
+
+    #[test]
+    fn test_medial_axis() {
+        let raster: BinaryRaster = "
+            ..........
+            .########.
+            .########.
+            .########.
+            ..........
+        ".parse().unwrap();
+        let (skeleton, radii) = raster.medial_axis();
+        assert_eq!(raster.skeletonized(), skeleton);
+        let mut truth = vec![0.; 50];
+        truth[22..27].fill(2.);
+        assert_eq!(truth, radii);
+        // the edges of the raster count as 0
+        let mut raster = BinaryRaster::new(3, 1);
+        raster.fill();
+        assert_eq!((raster.clone(), vec![1.; 3]), raster.medial_axis());
+    }
+
+    #[test]
+    fn test_hit_or_miss() {
+        let raster: BinaryRaster = "
+            ####..
+            ####..
+            ##....
+            ##...#
+        ".parse().unwrap();
+        // top left corners
+        let foreground: BinaryRaster = "
+            ...
+            .##
+            .#.
+        ".parse().unwrap();
+        let background: BinaryRaster = "
+            ###
+            #..
+            #..
+        ".parse().unwrap();
+        let corners: BinaryRaster = "
+            #.....
+            ......
+            ......
+            ......
+        ".parse().unwrap();
+        assert_eq!(corners, raster.hit_or_miss(&foreground, &background));
+        // isolated bits
+        let foreground: BinaryRaster = "#".parse().unwrap();
+        let background: BinaryRaster = "
+            ###
+            #.#
+            ###
+        ".parse().unwrap();
+        let isolated: BinaryRaster = "
+            ......
+            ......
+            ......
+            .....#
+        ".parse().unwrap();
+        assert_eq!(isolated, raster.hit_or_miss(&foreground, &background));
+    }
+
+    #[test]
+    fn test_outline() {
+        let raster: BinaryRaster = "
+            ######.
+            ######.
+            ######.
+            ####...
+            ####..#
+        ".parse().unwrap();
+        let outline: BinaryRaster = "
+            ######.
+            #....#.
+            #...##.
+            #..#...
+            ####..#
+        ".parse().unwrap();
+        assert_eq!(outline, raster.outline());
+    }
+}
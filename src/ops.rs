@@ -0,0 +1,111 @@
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use crate::BinaryRaster;
+
+impl BinaryRaster {
+    /// Replaces every chunk of self with op(chunk of self, chunk of other), panics if the rasters don't have the same size
+    fn combine_with(&mut self, other: &BinaryRaster, op: impl Fn(usize, usize) -> usize) {
+        assert_eq!(
+            (self.width(), self.height()), (other.width(), other.height()),
+            "bitwise operations require rasters of the same size"
+        );
+        for (bitline, other_bitline) in self.0.iter_mut().zip(other.0.iter()) {
+            bitline.combine_with(other_bitline, &op);
+        }
+    }
+}
+
+macro_rules! impl_bitwise_op {
+    ($op:ident, $op_fn:ident, $assign_op:ident, $assign_fn:ident, $chunk_op:expr) => {
+        impl $assign_op<&BinaryRaster> for BinaryRaster {
+            fn $assign_fn(&mut self, rhs: &BinaryRaster) {
+                self.combine_with(rhs, $chunk_op);
+            }
+        }
+
+        impl $op<&BinaryRaster> for BinaryRaster {
+            type Output = BinaryRaster;
+
+            fn $op_fn(mut self, rhs: &BinaryRaster) -> BinaryRaster {
+                self.$assign_fn(rhs);
+                self
+            }
+        }
+
+        impl $op<&BinaryRaster> for &BinaryRaster {
+            type Output = BinaryRaster;
+
+            fn $op_fn(self, rhs: &BinaryRaster) -> BinaryRaster {
+                self.clone().$op_fn(rhs)
+            }
+        }
+    };
+}
+
+impl_bitwise_op!(BitAnd, bitand, BitAndAssign, bitand_assign, |a, b| a & b);
+impl_bitwise_op!(BitOr, bitor, BitOrAssign, bitor_assign, |a, b| a | b);
+impl_bitwise_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, |a, b| a ^ b);
+
+impl Not for BinaryRaster {
+    type Output = BinaryRaster;
+
+    fn not(mut self) -> BinaryRaster {
+        self.invert();
+        self
+    }
+}
+
+impl Not for &BinaryRaster {
+    type Output = BinaryRaster;
+
+    fn not(self) -> BinaryRaster {
+        self.inverted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryRaster;
+
+    fn rasters() -> (BinaryRaster, BinaryRaster) {
+        let a = "
+            ##..
+            #.#.
+        ".parse().unwrap();
+        let b = "
+            #.#.
+            ..##
+        ".parse().unwrap();
+        (a, b)
+    }
+
+    #[test]
+    fn test_bitwise_ops() {
+        let (a, b) = rasters();
+        let and: BinaryRaster = "
+            #...
+            ..#.
+        ".parse().unwrap();
+        let or: BinaryRaster = "
+            ###.
+            #.##
+        ".parse().unwrap();
+        let xor: BinaryRaster = "
+            .##.
+            #..#
+        ".parse().unwrap();
+        assert_eq!(and, &a & &b);
+        assert_eq!(or, &a | &b);
+        assert_eq!(xor, a.clone() ^ &b);
+        let mut assigned = a.clone();
+        assigned ^= &b;
+        assert_eq!(xor, assigned);
+        assert_eq!(a.inverted(), !&a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_size_mismatch() {
+        let (a, _) = rasters();
+        let _ = &a | &BinaryRaster::new(3, 2);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use crate::{error::CollisionError, placement::PlacementStrategy, BinaryRaster};
+
+/// Identifies a sprite placed in a Packer, ids are never reused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PlacementId(usize);
+
+/// A sprite placed in a Packer at the top left position pos, along with the data it was placed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement<T> {
+    pub sprite: BinaryRaster,
+    pub pos: (usize, usize),
+    pub data: T,
+}
+
+/// Places sprites without overlap in a raster, keeping track of every placed sprite so it can be removed later
+/// The bits of the raster it's built from are obstacles that can't be removed
+#[derive(Debug, Clone)]
+pub struct Packer<T = ()> {
+    raster: BinaryRaster,
+    placements: BTreeMap<PlacementId, Placement<T>>,
+    next_id: usize,
+}
+
+impl<T> Packer<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        Packer::from_raster(BinaryRaster::new(width, height))
+    }
+
+    pub fn from_raster(raster: BinaryRaster) -> Self {
+        Packer { raster, placements: BTreeMap::new(), next_id: 0 }
+    }
+
+    /// The occupancy of the packer, obstacles and placed sprites combined
+    pub fn raster(&self) -> &BinaryRaster {
+        &self.raster
+    }
+
+    /// Places sprite at the first position in reading order where it fits without overlap, None if there's none
+    pub fn try_place(&mut self, sprite: &BinaryRaster, data: T) -> Option<PlacementId> {
+        let search_rect = (0, 0, self.raster.width(), self.raster.height());
+        let pos = self.raster.find_free_position(sprite, search_rect, 1)?;
+        Some(self.insert(sprite, pos, data))
+    }
+
+    /// Places sprite at the first position tried by strategy where it fits without overlap, None if there's none
+    pub fn try_place_with(&mut self, sprite: &BinaryRaster, strategy: &mut impl PlacementStrategy, data: T) -> Option<PlacementId> {
+        let pos = self.raster.find_position_with(sprite, strategy)?;
+        Some(self.insert(sprite, pos, data))
+    }
+
+    /// Places sprite at pos if it fits inside of the packer without overlap
+    /// An Err(CollisionError::Target) is returned if it doesn't, including when it goes past an edge
+    pub fn place_at(&mut self, sprite: &BinaryRaster, pos: (usize, usize), data: T) -> Result<PlacementId, CollisionError> {
+        let inside = pos.0 + sprite.width() <= self.raster.width() && pos.1 + sprite.height() <= self.raster.height();
+        if !inside || self.raster.collision_check_at(sprite, (pos.0 as isize, pos.1 as isize)) {
+            return Err(CollisionError::Target);
+        }
+        Ok(self.insert(sprite, pos, data))
+    }
+
+    /// Adds sprite to the raster at pos and records it, assuming it fits
+    fn insert(&mut self, sprite: &BinaryRaster, pos: (usize, usize), data: T) -> PlacementId {
+        self.raster.add_from(sprite, (pos.0 as isize, pos.1 as isize));
+        let id = PlacementId(self.next_id);
+        self.next_id += 1;
+        self.placements.insert(id, Placement { sprite: sprite.clone(), pos, data });
+        id
+    }
+
+    /// Removes a placed sprite, freeing its pixels, and returns it, None if the id isn't placed anymore
+    pub fn remove(&mut self, id: PlacementId) -> Option<Placement<T>> {
+        let placement = self.placements.remove(&id)?;
+        // placed sprites never overlap so this only clears the pixels of this one
+        self.raster.remove_from(&placement.sprite, (placement.pos.0 as isize, placement.pos.1 as isize));
+        Some(placement)
+    }
+
+    pub fn get(&self, id: PlacementId) -> Option<&Placement<T>> {
+        self.placements.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: PlacementId) -> Option<&mut T> {
+        self.placements.get_mut(&id).map(|placement| &mut placement.data)
+    }
+
+    /// The placed sprites in the order they were placed
+    pub fn iter(&self) -> impl Iterator<Item = (PlacementId, &Placement<T>)> {
+        self.placements.iter().map(|(&id, placement)| (id, placement))
+    }
+
+    /// The amount of placed sprites
+    pub fn len(&self) -> usize {
+        self.placements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Packer;
+    use crate::{BinaryRaster, CollisionError, SpiralPlacement};
+
+    #[test]
+    fn test_packer() {
+        let obstacles: BinaryRaster = "
+            #.....
+            ......
+            ......
+        ".parse().unwrap();
+        let mut packer = Packer::from_raster(obstacles.clone());
+        let block = BinaryRaster::from_fn(2, 2, |_, _| true);
+        let a = packer.try_place(&block, "a").unwrap();
+        let b = packer.try_place(&block, "b").unwrap();
+        assert_eq!((1, 0), packer.get(a).unwrap().pos);
+        assert_eq!((3, 0), packer.get(b).unwrap().pos);
+        assert_eq!(Err(CollisionError::Target), packer.place_at(&block, (2, 1), "c"));
+        assert_eq!(Err(CollisionError::Target), packer.place_at(&block, (5, 1), "c"));
+        let mut spiral = SpiralPlacement { center: (3, 2), step: 1, max_radius: 3 };
+        let d = packer.try_place_with(&BinaryRaster::from_fn(3, 1, |_, _| true), &mut spiral, "d").unwrap();
+        assert_eq!((2, 2), packer.get(d).unwrap().pos);
+        assert_eq!(None, packer.try_place(&block, "e"));
+        *packer.get_mut(a).unwrap() = "A";
+        assert_eq!(vec!["A", "b", "d"], packer.iter().map(|(_, placement)| placement.data).collect::<Vec<_>>());
+        assert_eq!("b", packer.remove(b).unwrap().data);
+        assert!(packer.remove(b).is_none());
+        assert_eq!(2, packer.len());
+        let e = packer.try_place(&block, "e").unwrap();
+        assert_ne!(b, e);
+        assert_eq!((3, 0), packer.get(e).unwrap().pos);
+        packer.remove(a);
+        packer.remove(d);
+        packer.remove(e);
+        assert!(packer.is_empty());
+        assert_eq!(&obstacles, packer.raster());
+    }
+}
@@ -0,0 +1,312 @@
+use std::f64::consts::TAU;
+use crate::{Anchor, BinaryRaster};
+
+impl BinaryRaster {
+    /// Calls visit with (x, y, w, h) for a set of empty rectangles containing every maximal empty rectangle,
+    /// using the largest rectangle in a histogram algorithm on the heights of the empty columns above each line
+    fn visit_empty_rects(&self, mut visit: impl FnMut(usize, usize, usize, usize)) {
+        let width = self.width();
+        let mut heights = vec![0; width];
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for (y, bitline) in self.0.iter().enumerate() {
+            for height in heights.iter_mut() {
+                *height += 1;
+            }
+            for (start, end) in bitline.runs() {
+                heights[start..end].fill(0);
+            }
+            // the stack holds (start, height) of the rectangles still growing to the right, with increasing heights
+            stack.clear();
+            for (x, &height) in heights.iter().chain(std::iter::once(&0)).enumerate() {
+                let mut start = x;
+                while let Some(&(rect_start, rect_height)) = stack.last() {
+                    if rect_height < height {
+                        break;
+                    }
+                    stack.pop();
+                    if rect_height > 0 {
+                        visit(rect_start, y + 1 - rect_height, x - rect_start, rect_height);
+                    }
+                    start = rect_start;
+                }
+                stack.push((start, height));
+            }
+        }
+    }
+
+    /// The biggest rectangle (x, y, w, h) of bits with a value of 0, None if there are none
+    pub fn largest_empty_rect(&self) -> Option<(usize, usize, usize, usize)> {
+        self.largest_empty_rect_with_aspect(0.)
+    }
+
+    /// The biggest rectangle (x, y, w, h) of bits with a value of 0 with w / h at least min_aspect, None if there are none
+    pub fn largest_empty_rect_with_aspect(&self, min_aspect: f64) -> Option<(usize, usize, usize, usize)> {
+        let mut best: Option<(usize, usize, usize, usize)> = None;
+        self.visit_empty_rects(|x, y, w, h| {
+            // the biggest rectangle with the aspect in a maximal rectangle keeps its whole width
+            let h = if min_aspect > 0. { h.min((w as f64 / min_aspect) as usize) } else { h };
+            if h > 0 && best.is_none_or(|(_, _, best_w, best_h)| w * h > best_w * best_h) {
+                best = Some((x, y, w, h));
+            }
+        });
+        best
+    }
+
+    /// The first top left position tried by strategy where sprite fits inside of self without colliding
+    /// Each row of sprite is only shifted once per distinct sub-word offset across the whole search
+    pub fn find_position_with(&self, sprite: &BinaryRaster, strategy: &mut impl PlacementStrategy) -> Option<(usize, usize)> {
+        let (max_x, max_y) = (self.width() as isize - sprite.width() as isize, self.height() as isize - sprite.height() as isize);
+        // the sprite must stay inside of self
+        let positions = strategy.candidates(self, sprite)
+            .filter(move |&(x, y)| (0..=max_x).contains(&x) && (0..=max_y).contains(&y));
+        self.collision_checks(sprite, positions)
+            .find(|&(_, collides)| !collides)
+            .map(|((x, y), _)| (x as usize, y as usize))
+    }
+
+    /// The first top left position in reading order where sprite fits inside of self without colliding,
+    /// trying every step pixels of the search rectangle (x, y, w, h) of top left positions
+    pub fn find_free_position(&self, sprite: &BinaryRaster, search_rect: (usize, usize, usize, usize), step: usize) -> Option<(usize, usize)> {
+        self.find_position_with(sprite, &mut ScanlinePlacement { search_rect, step })
+    }
+
+    /// The first top left position where sprite fits inside of self without colliding when its center follows
+    /// an Archimedean spiral going outward from center, with turns and probes step pixels apart, until max_radius
+    pub fn find_position_spiral(&self, sprite: &BinaryRaster, center: (usize, usize), step: usize, max_radius: usize) -> Option<(usize, usize)> {
+        self.find_position_with(sprite, &mut SpiralPlacement { center, step, max_radius })
+    }
+
+    /// The first top left position where sprite fits inside of self without colliding
+    /// out of attempts positions sampled uniformly with rng, seed it for reproducible layouts
+    #[cfg(feature = "rand")]
+    pub fn find_position_random<R: rand::Rng + ?Sized>(&self, sprite: &BinaryRaster, rng: &mut R, attempts: usize) -> Option<(usize, usize)> {
+        self.find_position_with(sprite, &mut RandomPlacement { rng, attempts })
+    }
+}
+
+/// An order in which to try the top left positions of a sprite when looking for a place where it fits,
+/// used by BinaryRaster::find_position_with which skips the positions where the sprite doesn't fit inside of the raster
+pub trait PlacementStrategy {
+    /// The top left positions to try for sprite in raster, in order
+    fn candidates<'a>(&'a mut self, raster: &'a BinaryRaster, sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a>;
+}
+
+/// Tries every step pixels of the search rectangle (x, y, w, h) of top left positions, in reading order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanlinePlacement {
+    pub search_rect: (usize, usize, usize, usize),
+    pub step: usize,
+}
+
+impl PlacementStrategy for ScanlinePlacement {
+    fn candidates<'a>(&'a mut self, raster: &'a BinaryRaster, sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a> {
+        let (x, y, w, h) = self.search_rect;
+        // the rectangle is clipped to the positions where the sprite stays inside of the raster
+        let x_end = (x + w).min((raster.width() + 1).saturating_sub(sprite.width()));
+        let y_end = (y + h).min((raster.height() + 1).saturating_sub(sprite.height()));
+        let step = self.step.max(1);
+        Box::new((y..y_end).step_by(step)
+            .flat_map(move |pos_y| (x..x_end).step_by(step).map(move |pos_x| (pos_x as isize, pos_y as isize))))
+    }
+}
+
+/// Moves the center of the sprite along an Archimedean spiral going outward from center,
+/// with turns and probes step pixels apart, until max_radius
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpiralPlacement {
+    pub center: (usize, usize),
+    pub step: usize,
+    pub max_radius: usize,
+}
+
+impl PlacementStrategy for SpiralPlacement {
+    fn candidates<'a>(&'a mut self, _raster: &'a BinaryRaster, sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a> {
+        let (center_x, center_y) = Anchor::Center.top_left(self.center, sprite.width(), sprite.height());
+        let (step, max_radius) = (self.step.max(1) as f64, self.max_radius as f64);
+        let mut angle: f64 = 0.;
+        let mut last = None;
+        Box::new(std::iter::from_fn(move || {
+            let radius = step * angle / TAU;
+            if radius > max_radius {
+                return None;
+            }
+            let pos = (center_x + (radius * angle.cos()).round() as isize, center_y + (radius * angle.sin()).round() as isize);
+            // advancing the angle so successive probes are about step pixels apart along the spiral
+            angle += step / radius.max(step);
+            Some(pos)
+        })
+        // the same position is only tried once in a row
+        .filter(move |&pos| last.replace(pos) != Some(pos)))
+    }
+}
+
+/// Tries attempts top left positions sampled uniformly with rng among the ones where the sprite stays inside of the raster
+#[cfg(feature = "rand")]
+#[derive(Debug)]
+pub struct RandomPlacement<'r, R: rand::Rng + ?Sized> {
+    pub rng: &'r mut R,
+    pub attempts: usize,
+}
+
+#[cfg(feature = "rand")]
+impl<R: rand::Rng + ?Sized> PlacementStrategy for RandomPlacement<'_, R> {
+    fn candidates<'a>(&'a mut self, raster: &'a BinaryRaster, sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a> {
+        // the amount of top left positions on each axis, none if the sprite is bigger than the raster
+        let positions = |raster_size: usize, sprite_size: usize| (raster_size + 1).checked_sub(sprite_size).filter(|&count| count > 0);
+        let (Some(max_x), Some(max_y)) = (positions(raster.width(), sprite.width()), positions(raster.height(), sprite.height())) else {
+            return Box::new(std::iter::empty());
+        };
+        let rng = &mut *self.rng;
+        Box::new((0..self.attempts).map(move |_| (rng.gen_range(0..max_x) as isize, rng.gen_range(0..max_y) as isize)))
+    }
+}
+
+/// Tries every step pixels of the top left positions where the sprite stays inside of the raster,
+/// in increasing order of the amount of bits with a value of 1 in the raster under the bounding rectangle of the sprite
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DensityPlacement {
+    pub step: usize,
+}
+
+impl PlacementStrategy for DensityPlacement {
+    fn candidates<'a>(&'a mut self, raster: &'a BinaryRaster, sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a> {
+        let (width, height) = (sprite.width(), sprite.height());
+        let integral = raster.integral();
+        let mut positions: Vec<(usize, (isize, isize))> = ScanlinePlacement { search_rect: (0, 0, raster.width(), raster.height()), step: self.step }
+            .candidates(raster, sprite)
+            .map(|(x, y)| (integral.count_ones_in(x as usize, y as usize, width, height), (x, y)))
+            .collect();
+        // the sort is stable so positions with the same density stay in reading order
+        positions.sort_by_key(|&(density, _)| density);
+        Box::new(positions.into_iter().map(|(_, pos)| pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::{BinaryRaster, DensityPlacement, PlacementStrategy, ScanlinePlacement};
+
+    #[test]
+    fn test_find_free_position() {
+        let raster: BinaryRaster = "
+            ##....
+            #..#..
+            ......
+            .....#
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert_eq!(Some((4, 0)), raster.find_free_position(&sprite, (0, 0, 6, 4), 1));
+        assert_eq!(Some((1, 1)), raster.find_free_position(&sprite, (0, 1, 6, 3), 1));
+        assert_eq!(Some((4, 1)), raster.find_free_position(&sprite, (0, 1, 6, 3), 2));
+        assert_eq!(None, raster.find_free_position(&sprite, (4, 2, 2, 2), 1));
+        assert_eq!(None, raster.find_free_position(&BinaryRaster::new(7, 1), (0, 0, 6, 4), 1));
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(300, 30, |_, _| rng.gen_bool(0.05));
+        let sprite = BinaryRaster::from_fn(70, 4, |_, _| rng.gen_bool(0.3));
+        let truth = (0..27).flat_map(|y| (0..231).map(move |x| (x, y)))
+            .find(|&(x, y)| !raster.collision_check_at(&sprite, (x as isize, y as isize)));
+        assert_eq!(truth, raster.find_free_position(&sprite, (0, 0, 300, 30), 1));
+    }
+
+    #[test]
+    fn test_find_position_spiral() {
+        let mut raster = BinaryRaster::new(40, 30);
+        raster.fill_rect(10, 10, 20, 10);
+        let sprite = BinaryRaster::from_fn(4, 4, |_, _| true);
+        assert_eq!(Some((2, 8)), BinaryRaster::new(10, 20).find_position_spiral(&sprite, (4, 10), 1, 0));
+        let (x, y) = raster.find_position_spiral(&sprite, (20, 15), 1, 40).unwrap();
+        assert!(!raster.collision_check_at(&sprite, (x as isize, y as isize)));
+        assert!(x + 4 <= 40 && y + 4 <= 30);
+        // the closest free positions are right above or below the filled rectangle
+        assert!(y == 6 || y == 20);
+        assert_eq!(None, raster.find_position_spiral(&sprite, (20, 15), 1, 4));
+        assert_eq!(None, raster.find_position_spiral(&BinaryRaster::new(41, 1), (20, 15), 1, 40));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_find_position_random() {
+        use rand::{rngs::StdRng, SeedableRng};
+        let mut raster = BinaryRaster::new(100, 50);
+        raster.fill_rect(0, 0, 100, 40);
+        let sprite = BinaryRaster::from_fn(10, 5, |_, _| true);
+        let (x, y) = raster.find_position_random(&sprite, &mut StdRng::seed_from_u64(7), 1000).unwrap();
+        assert!(y >= 40 && x + 10 <= 100 && y + 5 <= 50);
+        // the same seed gives the same position
+        assert_eq!(Some((x, y)), raster.find_position_random(&sprite, &mut StdRng::seed_from_u64(7), 1000));
+        assert_eq!(None, raster.find_position_random(&sprite, &mut StdRng::seed_from_u64(7), 0));
+        raster.fill();
+        assert_eq!(None, raster.find_position_random(&sprite, &mut StdRng::seed_from_u64(7), 100));
+        // sprites one pixel too wide or too tall have no position
+        let raster = BinaryRaster::new(10, 10);
+        for size in [(11, 2), (2, 11), (11, 11), (12, 2)] {
+            let sprite = BinaryRaster::new(size.0, size.1);
+            assert_eq!(None, raster.find_position_random(&sprite, &mut StdRng::seed_from_u64(7), 100));
+        }
+        assert_eq!(Some((0, 0)), raster.find_position_random(&BinaryRaster::new(10, 10), &mut StdRng::seed_from_u64(7), 1));
+    }
+
+    #[test]
+    fn test_find_position_with() {
+        let raster: BinaryRaster = "
+            #.......
+            #.#.....
+            ........
+            ##....##
+        ".parse().unwrap();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        assert_eq!(Some((3, 0)), raster.find_position_with(&sprite, &mut DensityPlacement { step: 1 }));
+        assert_eq!(Some((4, 0)), raster.find_position_with(&sprite, &mut DensityPlacement { step: 2 }));
+        // a custom strategy trying the positions from the bottom right corner
+        struct Backward;
+        impl PlacementStrategy for Backward {
+            fn candidates<'a>(&'a mut self, raster: &'a BinaryRaster, _sprite: &'a BinaryRaster) -> Box<dyn Iterator<Item = (isize, isize)> + 'a> {
+                let (width, height) = (raster.width() as isize, raster.height() as isize);
+                Box::new((0..height).rev().flat_map(move |y| (0..width).rev().map(move |x| (x, y))))
+            }
+        }
+        assert_eq!(Some((4, 2)), raster.find_position_with(&sprite, &mut Backward));
+        assert_eq!(
+            raster.find_free_position(&sprite, (1, 1, 5, 2), 1),
+            raster.find_position_with(&sprite, &mut ScanlinePlacement { search_rect: (1, 1, 5, 2), step: 1 }),
+        );
+    }
+
+    #[test]
+    fn test_largest_empty_rect() {
+        let raster: BinaryRaster = "
+            #.....#.
+            #...#...
+            #.......
+            ##......
+            #...###.
+        ".parse().unwrap();
+        assert_eq!(Some((2, 2, 6, 2)), raster.largest_empty_rect());
+        assert_eq!(Some((2, 2, 6, 2)), raster.largest_empty_rect_with_aspect(2.5));
+        assert_eq!(Some((1, 2, 7, 1)), raster.largest_empty_rect_with_aspect(5.));
+        let mut full = BinaryRaster::new(3, 2);
+        full.fill();
+        assert_eq!(None, full.largest_empty_rect());
+        // the same as checking every rectangle
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(20, 12, |_, _| rng.gen_bool(0.15));
+        let mut best_area = 0;
+        for (x, y) in (0..12).flat_map(|y| (0..20).map(move |x| (x, y))) {
+            for (w, h) in (1..=20 - x).flat_map(|w| (1..=12 - y).map(move |h| (w, h))) {
+                if w * h > best_area && raster.crop(x, y, w, h).count_ones() == 0 {
+                    best_area = w * h;
+                }
+            }
+        }
+        let (x, y, w, h) = raster.largest_empty_rect().unwrap();
+        assert_eq!(best_area, w * h);
+        assert_eq!(0, raster.crop(x, y, w, h).count_ones());
+    }
+}
@@ -0,0 +1,86 @@
+use std::borrow::Cow;
+use crate::{bitline::BitLine, BinaryRaster};
+
+/// A sprite along with its copies shifted right by every amount from 0 to usize::BITS - 1 and its bounding box,
+/// so it can be checked and added at any position with x >= 0 without shifting it again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreshiftedSprite {
+    shifted: Vec<BinaryRaster>,
+    bounding_box: Option<(usize, usize, usize, usize)>,
+}
+
+impl PreshiftedSprite {
+    pub fn new(sprite: &BinaryRaster) -> Self {
+        PreshiftedSprite {
+            shifted: (0..usize::BITS as usize).map(|amount| sprite.shifted_right(amount)).collect(),
+            bounding_box: sprite.bounding_box(),
+        }
+    }
+
+    /// The sprite the copies were made from
+    pub fn sprite(&self) -> &BinaryRaster {
+        &self.shifted[0]
+    }
+
+    /// The smallest rectangle (x0, y0, x1, y1) containing every bit with a value of 1 of the sprite, bounds included
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        self.bounding_box
+    }
+
+    /// The copy of the sprite aligned with the chunks of a raster when placed at x >= 0
+    fn aligned(&self, x: usize) -> &BinaryRaster {
+        &self.shifted[BitLine::chunked(x).1]
+    }
+}
+
+impl BinaryRaster {
+    /// Same as collision_check_at(sprite.sprite(), pos) without shifting the sprite when pos.0 >= 0
+    pub fn collision_check_at_preshifted(&self, sprite: &PreshiftedSprite, pos: (isize, isize)) -> bool {
+        if pos.0 < 0 {
+            return self.collision_check_at(sprite.sprite(), pos);
+        }
+        let aligned = sprite.aligned(pos.0 as usize);
+        self.collision_check_lines(sprite.sprite(), pos, |line_i| Cow::Borrowed(&aligned.0[line_i]))
+    }
+
+    /// Same as add_from(sprite.sprite(), pos) without shifting the sprite when pos.0 >= 0
+    pub fn add_from_preshifted(&mut self, sprite: &PreshiftedSprite, pos: (isize, isize)) {
+        if pos.0 < 0 {
+            self.add_from(sprite.sprite(), pos);
+            return;
+        }
+        let segment_offset = BitLine::chunked(pos.0 as usize).0;
+        let height = self.height() as isize;
+        for (line_i, bitline) in sprite.aligned(pos.0 as usize).0.iter().enumerate() {
+            let y = line_i as isize + pos.1;
+            if (0..height).contains(&y) {
+                self.0[y as usize].combine_at(bitline, segment_offset, |a, b| a | b);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::PreshiftedSprite;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_preshifted_sprite() {
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(300, 30, |_, _| rng.gen_bool(0.01));
+        let sprite = BinaryRaster::from_fn(70, 6, |_, _| rng.gen_bool(0.3));
+        let preshifted = PreshiftedSprite::new(&sprite);
+        assert_eq!(&sprite, preshifted.sprite());
+        assert_eq!(sprite.bounding_box(), preshifted.bounding_box());
+        for _ in 0..300 {
+            let pos = (rng.gen_range(-75..305), rng.gen_range(-7..32));
+            assert_eq!(raster.collision_check_at(&sprite, pos), raster.collision_check_at_preshifted(&preshifted, pos));
+            let (mut added, mut added_preshifted) = (raster.clone(), raster.clone());
+            added.add_from(&sprite, pos);
+            added_preshifted.add_from_preshifted(&preshifted, pos);
+            assert_eq!(added, added_preshifted);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{BinaryRaster, DownscaleMode};
+
+/// Levels of a raster where each level is the previous one scaled down by 2 with DownscaleMode::Any,
+/// so a bit of the level k is set if any bit of the 2^k x 2^k block it covers in the raster is set
+/// The empty regions of the raster can be skipped a whole block at a time when checking for collisions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterPyramid {
+    levels: Vec<BinaryRaster>,
+}
+
+impl RasterPyramid {
+    pub fn new(raster: &BinaryRaster) -> Self {
+        let mut levels = vec![raster.clone()];
+        while let Some(last) = levels.last().filter(|last| last.width() > 1 || last.height() > 1) {
+            levels.push(last.scaled_down(2, DownscaleMode::Any));
+        }
+        RasterPyramid { levels }
+    }
+
+    /// The levels from the raster itself to a single bit (for a non-empty raster)
+    pub fn levels(&self) -> &[BinaryRaster] {
+        &self.levels
+    }
+
+    /// The raster the pyramid was built from
+    pub fn raster(&self) -> &BinaryRaster {
+        &self.levels[0]
+    }
+
+    /// False if other placed at pos can't collide with the raster according to the level k,
+    /// checking the blocks covered by the extents of the lines of other in each row of blocks
+    fn may_collide_at_level(&self, k: usize, extents: &[Option<(usize, usize)>], pos: (isize, isize)) -> bool {
+        let level = &self.levels[k];
+        // the first and last block covered by the lines of other in the current row of blocks
+        let mut row: Option<(isize, isize, isize)> = None;
+        let blocks = extents.iter().enumerate().filter_map(|(line_i, extent)| {
+            let (start, end) = (*extent)?;
+            Some(((line_i as isize + pos.1) >> k, (pos.0 + start as isize) >> k, (pos.0 + end as isize) >> k))
+        });
+        let covers_set_bit = |(y, start, end): (isize, isize, isize)| {
+            y >= 0 && end >= 0 && level.0.get(y as usize)
+                .is_some_and(|bitline| bitline.count_ones_range(start.max(0) as usize, end as usize + 1) > 0)
+        };
+        for (y, start, end) in blocks {
+            match row {
+                Some((row_y, row_start, row_end)) if row_y == y => row = Some((y, row_start.min(start), row_end.max(end))),
+                Some(previous) => {
+                    if covers_set_bit(previous) {
+                        return true;
+                    }
+                    row = Some((y, start, end));
+                },
+                None => row = Some((y, start, end)),
+            }
+        }
+        row.is_some_and(covers_set_bit)
+    }
+
+    /// Same as raster().collision_check_at(other, pos), going down the levels from the coarsest one
+    /// and returning false as soon as a level shows that other only covers empty blocks
+    pub fn collision_check_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> bool {
+        let extents: Vec<Option<(usize, usize)>> = other.0.iter()
+            .map(|bitline| Some((bitline.start()?, bitline.end()?)))
+            .collect();
+        (1..self.levels.len()).rev().all(|k| self.may_collide_at_level(k, &extents, pos))
+            && self.levels[0].collision_check_at(other, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::RasterPyramid;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_levels() {
+        let raster: BinaryRaster = "
+            .....
+            ...#.
+            .....
+        ".parse().unwrap();
+        let pyramid = RasterPyramid::new(&raster);
+        let level_1: BinaryRaster = "
+            .#.
+            ...
+        ".parse().unwrap();
+        let level_2: BinaryRaster = "
+            #.
+        ".parse().unwrap();
+        let level_3: BinaryRaster = "#".parse().unwrap();
+        assert_eq!(&[raster.clone(), level_1, level_2, level_3], pyramid.levels());
+        assert_eq!(&raster, pyramid.raster());
+    }
+
+    #[test]
+    fn test_collision_check_at() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::from_fn(300, 80, |_, _| rng.gen_bool(0.001));
+        raster.fill_rect(150, 30, 20, 10);
+        let pyramid = RasterPyramid::new(&raster);
+        let sprite = BinaryRaster::from_fn(30, 9, |_, _| rng.gen_bool(0.3));
+        for _ in 0..1000 {
+            let pos = (rng.gen_range(-35..305), rng.gen_range(-10..85));
+            assert_eq!(raster.collision_check_at(&sprite, pos), pyramid.collision_check_at(&sprite, pos));
+        }
+    }
+}
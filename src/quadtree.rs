@@ -0,0 +1,246 @@
+use crate::BinaryRaster;
+
+/// Quadrants at most this wide are not subdivided, a chunk of bits is checked as fast as a node
+const LEAF_SIZE: usize = usize::BITS as usize;
+
+/// A square quadrant of the raster, the parts of it outside of the raster being read as 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Empty,
+    Full,
+    /// A quadrant with bits of both values, subdivided in its top left, top right, bottom left and bottom right quadrants
+    /// unless it's a leaf
+    Mixed(Option<Box<[Node; 4]>>),
+}
+
+/// The rectangle (x, y, w, h) of a quadrant, with x and y possibly past the end of the raster
+type Rect = (usize, usize, usize, usize);
+
+/// The intersection of 2 rectangles (x, y, w, h), None if it's empty
+fn intersection(a: Rect, b: Rect) -> Option<Rect> {
+    let (x0, y0) = (a.0.max(b.0), a.1.max(b.1));
+    let (x1, y1) = ((a.0 + a.2).min(b.0 + b.2), (a.1 + a.3).min(b.1 + b.3));
+    (x0 < x1 && y0 < y1).then(|| (x0, y0, x1 - x0, y1 - y0))
+}
+
+/// The amount of bits with a value of 1 of raster in the rectangle (x, y, w, h)
+fn count_ones_in(raster: &BinaryRaster, (x, y, w, h): Rect) -> usize {
+    raster.0.iter().skip(y).take(h).map(|bitline| bitline.count_ones_range(x, x + w)).sum()
+}
+
+/// The 4 quadrants of a square of the given size at (x, y), in the order of Node::Mixed
+fn quadrants((x, y, size, _): Rect) -> [Rect; 4] {
+    let half = size / 2;
+    [(x, y, half, half), (x + half, y, half, half), (x, y + half, half, half), (x + half, y + half, half, half)]
+}
+
+/// The mask of the first w bits of a chunk, for w <= usize::BITS
+fn low_bits(w: usize) -> usize {
+    if w >= usize::BITS as usize { usize::MAX } else { (1 << w) - 1 }
+}
+
+/// An index of the quadrants of a raster that are entirely 0 or entirely 1,
+/// so the queries can skip these quadrants without looking at their bits
+/// The index keeps a copy of the raster, add_from and update_rect change it and only re-derive the quadrants they cover
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadTreeIndex {
+    raster: BinaryRaster,
+    root: Node,
+    size: usize,
+}
+
+impl QuadTreeIndex {
+    pub fn new(raster: &BinaryRaster) -> Self {
+        let size = raster.width().max(raster.height()).max(1).next_power_of_two();
+        let root = QuadTreeIndex::build(raster, (0, 0, size, size));
+        QuadTreeIndex { raster: raster.clone(), root, size }
+    }
+
+    fn build(raster: &BinaryRaster, rect: Rect) -> Node {
+        let Some(inside) = intersection(rect, (0, 0, raster.width(), raster.height())) else {
+            return Node::Empty;
+        };
+        let count = count_ones_in(raster, inside);
+        if count == 0 {
+            Node::Empty
+        } else if count == rect.2 * rect.3 {
+            Node::Full
+        } else if rect.2 <= LEAF_SIZE {
+            Node::Mixed(None)
+        } else {
+            Node::Mixed(Some(Box::new(quadrants(rect).map(|quadrant| QuadTreeIndex::build(raster, quadrant)))))
+        }
+    }
+
+    /// The raster the index was built from
+    pub fn raster(&self) -> &BinaryRaster {
+        &self.raster
+    }
+
+    /// Calls visit with the intersections of rect and the non empty nodes covering it, along with whether the node is full,
+    /// stopping as soon as visit returns true and returning true in that case
+    fn any_in(&self, rect: Rect, mut visit: impl FnMut(Rect, bool) -> bool) -> bool {
+        let mut stack = vec![(&self.root, (0, 0, self.size, self.size))];
+        while let Some((node, node_rect)) = stack.pop() {
+            let Some(covered) = intersection(rect, node_rect) else {
+                continue;
+            };
+            match node {
+                Node::Empty => {},
+                Node::Full => if visit(covered, true) {
+                    return true;
+                },
+                Node::Mixed(None) => if visit(covered, false) {
+                    return true;
+                },
+                Node::Mixed(Some(children)) => stack.extend(children.iter().zip(quadrants(node_rect))),
+            }
+        }
+        false
+    }
+
+    /// True if every bit of the rectangle (x, y, w, h) has a value of 0, bits outside of the raster being read as 0
+    pub fn is_rect_empty(&self, x: usize, y: usize, w: usize, h: usize) -> bool {
+        !self.any_in((x, y, w, h), |covered, full| full || count_ones_in(&self.raster, covered) > 0)
+    }
+
+    /// Same as raster().collision_check_at(other, pos), skipping the empty quadrants covered by other
+    /// and only checking that other has a bit with a value of 1 in the full ones
+    pub fn collision_check_at(&self, other: &BinaryRaster, pos: (isize, isize)) -> bool {
+        let Some(footprint) = self.footprint(other, pos) else {
+            return false;
+        };
+        self.any_in(footprint, |(x, y, w, h), full| {
+            // the part of other covered by the quadrant, in the coordinates of other
+            let (other_x, other_y) = ((x as isize - pos.0) as usize, (y as isize - pos.1) as usize);
+            if full {
+                return count_ones_in(other, (other_x, other_y, w, h)) > 0;
+            }
+            // a leaf is at most a chunk wide, so each of its lines is checked in a single word
+            let mask = low_bits(w);
+            self.raster.0[y..y + h].iter().zip(&other.0[other_y..other_y + h])
+                .any(|(line, other_line)| line.word_at(x) & other_line.word_at(other_x) & mask != 0)
+        })
+    }
+
+    /// Adds sprite at pos to the indexed raster, same as BinaryRaster::add_from, and updates the quadrants it covers
+    pub fn add_from(&mut self, sprite: &BinaryRaster, pos: (isize, isize)) {
+        if let Some(footprint) = self.footprint(sprite, pos) {
+            self.raster.add_from(sprite, pos);
+            self.update_nodes(footprint);
+        }
+    }
+
+    /// The bounding box of sprite placed at pos as a rectangle (x, y, w, h) clipped to the raster, None if it's empty
+    fn footprint(&self, sprite: &BinaryRaster, pos: (isize, isize)) -> Option<Rect> {
+        let (x0, y0, x1, y1) = sprite.bounding_box()?;
+        let (left, top) = (pos.0 + x0 as isize, pos.1 + y0 as isize);
+        let (right, bottom) = (pos.0 + x1 as isize + 1, pos.1 + y1 as isize + 1);
+        if right <= 0 || bottom <= 0 {
+            return None;
+        }
+        let (left, top) = (left.max(0) as usize, top.max(0) as usize);
+        let rect = (left, top, (right as usize).saturating_sub(left), (bottom as usize).saturating_sub(top));
+        intersection(rect, (0, 0, self.raster.width(), self.raster.height()))
+    }
+
+    /// Copies the rectangle (x, y, w, h) of raster into the indexed raster and updates the quadrants it covers,
+    /// to keep the index in sync with a raster of the same size that only changed in this rectangle
+    pub fn update_rect(&mut self, raster: &BinaryRaster, x: usize, y: usize, w: usize, h: usize) {
+        self.raster.clear_rect(x, y, w, h);
+        self.raster.add_from(&raster.crop(x, y, w, h), (x as isize, y as isize));
+        self.update_nodes((x, y, w, h));
+    }
+
+    /// Re-derives the nodes intersecting the rectangle dirty from the indexed raster
+    fn update_nodes(&mut self, dirty: Rect) {
+        let mut root = std::mem::replace(&mut self.root, Node::Empty);
+        QuadTreeIndex::update(&mut root, &self.raster, (0, 0, self.size, self.size), dirty);
+        self.root = root;
+    }
+
+    fn update(node: &mut Node, raster: &BinaryRaster, rect: Rect, dirty: Rect) {
+        if intersection(rect, dirty).is_none() {
+            return;
+        }
+        let covered = intersection(rect, dirty) == Some(rect);
+        if covered || rect.2 <= LEAF_SIZE {
+            *node = QuadTreeIndex::build(raster, rect);
+            return;
+        }
+        // an uniform node is split in 4 quadrants of the same value so only the ones intersecting dirty are rebuilt
+        let mut children = match std::mem::replace(node, Node::Empty) {
+            Node::Mixed(Some(children)) => children,
+            uniform => Box::new([uniform.clone(), uniform.clone(), uniform.clone(), uniform]),
+        };
+        for (child, quadrant) in children.iter_mut().zip(quadrants(rect)) {
+            QuadTreeIndex::update(child, raster, quadrant, dirty);
+        }
+        *node = match &*children {
+            [Node::Empty, Node::Empty, Node::Empty, Node::Empty] => Node::Empty,
+            [Node::Full, Node::Full, Node::Full, Node::Full] => Node::Full,
+            _ => Node::Mixed(Some(children)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::QuadTreeIndex;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_is_rect_empty() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::new(500, 300);
+        raster.fill_rect(100, 50, 200, 130);
+        raster.add_from(&BinaryRaster::from_fn(100, 100, |_, _| rng.gen_bool(0.01)), (350, 150));
+        let index = QuadTreeIndex::new(&raster);
+        assert_eq!(&raster, index.raster());
+        for _ in 0..300 {
+            let (x, y) = (rng.gen_range(0..520), rng.gen_range(0..320));
+            let (w, h) = (rng.gen_range(0..150), rng.gen_range(0..150));
+            assert_eq!(raster.crop(x, y, w, h).count_ones() == 0, index.is_rect_empty(x, y, w, h));
+        }
+    }
+
+    #[test]
+    fn test_collision_check_at() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::new(500, 300);
+        raster.fill_rect(100, 50, 200, 130);
+        raster.add_from(&BinaryRaster::from_fn(100, 100, |_, _| rng.gen_bool(0.01)), (350, 150));
+        raster.fill_rect(450, 260, 50, 40);
+        raster.set(499, 299, false);
+        let index = QuadTreeIndex::new(&raster);
+        let sprite = BinaryRaster::from_fn(40, 12, |_, _| rng.gen_bool(0.2));
+        for _ in 0..1000 {
+            let pos = (rng.gen_range(-45..505), rng.gen_range(-15..305));
+            assert_eq!(raster.collision_check_at(&sprite, pos), index.collision_check_at(&sprite, pos));
+        }
+    }
+
+    #[test]
+    fn test_updates() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::new(300, 200);
+        raster.fill_rect(0, 0, 128, 128);
+        let mut index = QuadTreeIndex::new(&raster);
+        for _ in 0..30 {
+            let sprite = BinaryRaster::from_fn(rng.gen_range(1..90), rng.gen_range(1..90), |_, _| rng.gen_bool(0.9));
+            let pos = (rng.gen_range(-90..310), rng.gen_range(-90..210));
+            raster.add_from(&sprite, pos);
+            index.add_from(&sprite, pos);
+            assert_eq!(QuadTreeIndex::new(&raster), index);
+            let (x, y, w, h) = (rng.gen_range(0..310), rng.gen_range(0..210), rng.gen_range(0..150), rng.gen_range(0..150));
+            if rng.gen_bool(0.5) {
+                raster.clear_rect(x, y, w, h);
+            } else {
+                raster.fill_rect(x, y, w, h);
+            }
+            index.update_rect(&raster, x, y, w, h);
+            assert_eq!(QuadTreeIndex::new(&raster), index);
+        }
+    }
+}
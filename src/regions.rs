@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use crate::BinaryRaster;
+
+/// The bits start..end of the line y, all with a value of 1
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    y: usize,
+    start: usize,
+    end: usize,
+}
+
+/// The 8 neighbours of a bit clockwise from the right one
+const NEIGHBOURS: [(isize, isize); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+/// The representative of the set of i, halving the path to it along the way
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+impl BinaryRaster {
+    /// The runs of each connected region of bits with a value of 1, the regions being ordered by their first bit
+    /// Bits only touching diagonally are connected if diagonal is true
+    fn region_runs(&self, diagonal: bool) -> Vec<Vec<Run>> {
+        let mut runs = Vec::new();
+        let mut parents = Vec::new();
+        let mut previous_line = 0..0;
+        for (y, bitline) in self.0.iter().enumerate() {
+            let line_start = runs.len();
+            runs.extend(bitline.runs().map(|(start, end)| Run { y, start, end }));
+            parents.extend(line_start..runs.len());
+            // both lines are sorted, so the overlapping runs are found in a single pass
+            let (mut i, mut j) = (previous_line.start, line_start);
+            while i < previous_line.end && j < runs.len() {
+                let (above, below) = (runs[i], runs[j]);
+                if above.start < below.end + diagonal as usize && below.start < above.end + diagonal as usize {
+                    let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                    parents[a.max(b)] = a.min(b);
+                }
+                if above.end < below.end {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            previous_line = line_start..runs.len();
+        }
+        let mut region_indices = HashMap::new();
+        let mut regions: Vec<Vec<Run>> = Vec::new();
+        for (i, &run) in runs.iter().enumerate() {
+            let root = find(&mut parents, i);
+            let region_i = *region_indices.entry(root).or_insert_with(|| {
+                regions.push(Vec::new());
+                regions.len() - 1
+            });
+            regions[region_i].push(run);
+        }
+        regions
+    }
+
+    /// Returns every 8-connected region of bits with a value of 1 cropped to its bounding box, along with its position in self
+    /// The regions are ordered by their first bit, top to bottom and left to right
+    pub fn components(&self) -> Vec<(BinaryRaster, (usize, usize))> {
+        self.region_runs(true).into_iter().map(|runs| {
+            let x0 = runs.iter().map(|run| run.start).min().unwrap_or(0);
+            let x1 = runs.iter().map(|run| run.end).max().unwrap_or(0);
+            let (y0, y1) = (runs[0].y, runs[runs.len() - 1].y);
+            let mut component = BinaryRaster::new(x1 - x0, y1 - y0 + 1);
+            for run in runs {
+                component.0[run.y - y0].fill_range(run.start - x0, run.end - x0);
+            }
+            (component, (x0, y0))
+        }).collect()
+    }
+
+    /// Returns the outer boundary of every 8-connected region of bits with a value of 1,
+    /// as the bits of the boundary in clockwise order starting from the first bit of the region
+    pub fn contours(&self) -> Vec<Vec<(usize, usize)>> {
+        self.region_runs(true).iter().map(|runs| self.trace_contour((runs[0].start, runs[0].y), 4)).collect()
+    }
+
+    /// Same as contours, followed by the boundary of every hole, a 4-connected region of bits with a value of 0 not touching the edges,
+    /// in counterclockwise order starting from the bit above the first bit of the hole
+    pub fn contours_with_holes(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut contours = self.contours();
+        contours.extend(self.holes().iter().map(|runs| self.trace_contour((runs[0].start, runs[0].y - 1), 2)));
+        contours
+    }
+
+    /// Follows the boundary of the region of start with the Moore neighbourhood tracing algorithm,
+    /// backtrack being the direction of a neighbour of start with a value of 0 in NEIGHBOURS
+    /// Stops when coming back to start and leaving it the same way as the first time (Jacob's stopping criterion)
+    fn trace_contour(&self, start: (usize, usize), backtrack: usize) -> Vec<(usize, usize)> {
+        let is_set = |(x, y): (isize, isize)| x >= 0 && y >= 0 && self.get(x as usize, y as usize);
+        let start = (start.0 as isize, start.1 as isize);
+        let mut contour = vec![start];
+        let (mut current, mut search_start) = (start, backtrack + 1);
+        let mut first_move = None;
+        loop {
+            let step = |d: usize| (current.0 + NEIGHBOURS[d].0, current.1 + NEIGHBOURS[d].1);
+            // an isolated bit has no neighbour to move to
+            let Some(d) = (search_start..search_start + 8).map(|d| d % 8).find(|&d| is_set(step(d))) else {
+                break;
+            };
+            if current == start && first_move == Some(d) {
+                break;
+            }
+            first_move.get_or_insert(d);
+            // the last neighbour checked before the next bit has a value of 0, the search continues from there
+            let previous = step((d + 7) % 8);
+            current = step(d);
+            let backtrack = (previous.0 - current.0, previous.1 - current.1);
+            search_start = NEIGHBOURS.iter().position(|&n| n == backtrack).unwrap_or(0) + 1;
+            contour.push(current);
+        }
+        if contour.len() > 1 {
+            // the contour ends where it started
+            contour.pop();
+        }
+        contour.into_iter().map(|(x, y)| (x as usize, y as usize)).collect()
+    }
+
+    /// The runs of every 4-connected region of bits with a value of 0 that doesn't touch the edges of the raster
+    fn holes(&self) -> Vec<Vec<Run>> {
+        let height = self.height();
+        let mut regions = self.inverted().region_runs(false);
+        regions.retain(|runs| {
+            !runs.iter().any(|run| run.y == 0 || run.y == height - 1 || run.start == 0 || run.end == self.0[run.y].len())
+        });
+        regions
+    }
+
+    /// Sets every bit of the 4-connected regions of bits with a value of 0 that don't touch the edges of the raster to 1
+    pub fn fill_holes(&mut self) {
+        for runs in self.holes() {
+            for run in runs {
+                self.0[run.y].fill_range(run.start, run.end);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_components() {
+        let raster: BinaryRaster = "
+            ##..#.
+            .#.##.
+            ..#...
+            #....#
+            ##...#
+        ".parse().unwrap();
+        let components: Vec<(BinaryRaster, (usize, usize))> = vec![
+            ("
+                ##..#
+                .#.##
+                ..#..
+            ".parse().unwrap(), (0, 0)),
+            ("
+                #.
+                ##
+            ".parse().unwrap(), (0, 3)),
+            ("
+                #
+                #
+            ".parse().unwrap(), (5, 3)),
+        ];
+        assert_eq!(components, raster.components());
+        assert!(BinaryRaster::new(3, 3).components().is_empty());
+    }
+
+    #[test]
+    fn test_contours() {
+        let raster: BinaryRaster = "
+            .####.
+            .#..#.
+            .####.
+            ......
+            #.....
+        ".parse().unwrap();
+        let outer = vec![(1, 0), (2, 0), (3, 0), (4, 0), (4, 1), (4, 2), (3, 2), (2, 2), (1, 2), (1, 1)];
+        assert_eq!(vec![outer.clone(), vec![(0, 4)]], raster.contours());
+        let inner = vec![(2, 0), (1, 1), (2, 2), (3, 2), (4, 1), (3, 0)];
+        assert_eq!(vec![outer, vec![(0, 4)], inner], raster.contours_with_holes());
+        // a bit on a diagonal line is visited once in each direction
+        let raster: BinaryRaster = "
+            #..
+            .##
+        ".parse().unwrap();
+        assert_eq!(vec![vec![(0, 0), (1, 1), (2, 1), (1, 1)]], raster.contours());
+    }
+
+    #[test]
+    fn test_fill_holes() {
+        let mut raster: BinaryRaster = "
+            .#####..
+            .#...#..
+            .#.#.###
+            .#...#.#
+            .####.##
+            ......#.
+        ".parse().unwrap();
+        let filled: BinaryRaster = "
+            .#####..
+            .#####..
+            .#######
+            .#######
+            .####.##
+            ......#.
+        ".parse().unwrap();
+        raster.fill_holes();
+        assert_eq!(filled, raster);
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::VecDeque;
+use crate::{bitline::BitLine, BinaryRaster};
+
+/// The content of a raster at some point, to restore it later
+/// The chunks of each line are shared with the raster and only copied when the raster modifies the line,
+/// so taking a snapshot costs a reference count per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    lines: Vec<BitLine>,
+}
+
+impl BinaryRaster {
+    /// Saves the current content of the raster
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot { lines: self.0.clone() }
+    }
+
+    /// Brings the raster back to the content it had when snapshot was taken, including its size
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.0.clone_from(&snapshot.lines);
+    }
+}
+
+/// The snapshots of a raster before each of the last capacity stamps, to undo them from the most recent one
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl UndoStack {
+    pub fn new(capacity: usize) -> Self {
+        UndoStack { snapshots: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Same as raster.add_from(source, pos), remembering the content of raster before so it can be undone,
+    /// the oldest stamp can't be undone anymore if the stack is full
+    pub fn add_from(&mut self, raster: &mut BinaryRaster, source: &BinaryRaster, pos: (isize, isize)) {
+        self.push(raster.snapshot());
+        raster.add_from(source, pos);
+    }
+
+    /// Same as raster.add_from_checked(source, pos), only remembering the stamp if it's done
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked(&mut self, raster: &mut BinaryRaster, source: &BinaryRaster, pos: (isize, isize)) -> Result<(), ()> {
+        let snapshot = raster.snapshot();
+        raster.add_from_checked(source, pos)?;
+        self.push(snapshot);
+        Ok(())
+    }
+
+    /// Remembers snapshot as the content before the most recent stamp
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// Restores raster to its content before the most recent stamp, false if there's no stamp left to undo
+    pub fn undo(&mut self, raster: &mut BinaryRaster) -> bool {
+        let Some(snapshot) = self.snapshots.pop_back() else {
+            return false;
+        };
+        raster.restore(&snapshot);
+        true
+    }
+
+    /// The amount of stamps that can be undone
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Forgets every stamp
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use super::UndoStack;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_snapshot() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::from_fn(150, 20, |_, _| rng.gen_bool(0.2));
+        let original = raster.clone();
+        let snapshot = raster.snapshot();
+        raster.fill_rect(10, 5, 100, 3);
+        raster.set(149, 19, true);
+        let modified = raster.clone();
+        assert_ne!(original, raster);
+        raster.restore(&snapshot);
+        assert_eq!(original, raster);
+        raster.pad(2, 2, 2, 2);
+        raster.restore(&snapshot);
+        assert_eq!(original, raster);
+        let mut other = BinaryRaster::new(3, 3);
+        other.restore(&modified.snapshot());
+        assert_eq!(modified, other);
+    }
+
+    #[test]
+    fn test_undo_stack() {
+        let mut raster = BinaryRaster::new(6, 3);
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        let mut undo = UndoStack::new(2);
+        let states: Vec<BinaryRaster> = [(0, 0), (2, 1), (4, 0)].into_iter().map(|pos| {
+            let before = raster.clone();
+            undo.add_from(&mut raster, &sprite, pos);
+            before
+        }).collect();
+        assert_eq!(Err(()), undo.add_from_checked(&mut raster, &sprite, (1, 0)));
+        assert_eq!(2, undo.len());
+        assert!(undo.undo(&mut raster));
+        assert_eq!(states[2], raster);
+        assert!(undo.undo(&mut raster));
+        assert_eq!(states[1], raster);
+        assert!(!undo.undo(&mut raster));
+        assert_eq!(states[1], raster);
+        assert!(undo.is_empty());
+        let mut disabled = UndoStack::new(0);
+        disabled.add_from(&mut raster, &sprite, (4, 1));
+        assert!(!disabled.undo(&mut raster));
+    }
+}
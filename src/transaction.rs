@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use crate::{bitline::BitLine, BinaryRaster};
+
+/// Sprites stamped on a raster that can be committed or rolled back together
+/// Only the lines touched by the stamped sprites are copied, the first time they're touched,
+/// so trying a layout doesn't need a clone of the whole raster
+/// The stamps are rolled back if the transaction is dropped without being committed
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    raster: &'a mut BinaryRaster,
+    /// The lines of the raster as they were before the transaction, by index
+    saved: BTreeMap<usize, BitLine>,
+}
+
+impl BinaryRaster {
+    /// Starts a transaction stamping sprites on self
+    pub fn begin(&mut self) -> Transaction<'_> {
+        Transaction { raster: self, saved: BTreeMap::new() }
+    }
+}
+
+impl<'a> Transaction<'a> {
+    /// The raster with every sprite stamped so far
+    pub fn raster(&self) -> &BinaryRaster {
+        self.raster
+    }
+
+    /// Saves the lines of the raster that source placed at pos can change, if they're not saved already
+    fn save_lines(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        let height = self.raster.height() as isize;
+        for (line_i, bitline) in source.0.iter().enumerate() {
+            let y = line_i as isize + pos.1;
+            if bitline.extent().is_some() && (0..height).contains(&y) {
+                let line = &self.raster.0[y as usize];
+                self.saved.entry(y as usize).or_insert_with(|| line.clone());
+            }
+        }
+    }
+
+    /// Same as BinaryRaster::add_from, undone by a rollback
+    pub fn add_from(&mut self, source: &BinaryRaster, pos: (isize, isize)) {
+        self.save_lines(source, pos);
+        self.raster.add_from(source, pos);
+    }
+
+    /// Same as BinaryRaster::add_from_checked, also checking for collisions with the sprites stamped so far
+    #[allow(clippy::result_unit_err)]
+    pub fn add_from_checked(&mut self, source: &BinaryRaster, pos: (isize, isize)) -> Result<(), ()> {
+        if self.raster.collision_check_at(source, pos) {
+            return Err(());
+        }
+        self.add_from(source, pos);
+        Ok(())
+    }
+
+    /// Keeps every sprite stamped during the transaction
+    pub fn commit(mut self) {
+        self.saved.clear();
+    }
+
+    /// Restores the raster as it was before the transaction
+    pub fn rollback(self) {}
+}
+
+impl Drop for Transaction<'_> {
+    fn drop(&mut self) {
+        for (y, line) in std::mem::take(&mut self.saved) {
+            self.raster.0[y] = line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::BinaryRaster;
+
+    #[test]
+    fn test_transaction() {
+        let mut raster: BinaryRaster = "
+            #.....
+            ......
+            ......
+        ".parse().unwrap();
+        let original = raster.clone();
+        let sprite: BinaryRaster = "
+            ##
+            ##
+        ".parse().unwrap();
+        let stamped: BinaryRaster = "
+            #...##
+            ##..##
+            ##....
+        ".parse().unwrap();
+        let mut transaction = raster.begin();
+        transaction.add_from(&sprite, (4, 0));
+        assert_eq!(Err(()), transaction.add_from_checked(&sprite, (3, -1)));
+        assert_eq!(Ok(()), transaction.add_from_checked(&sprite, (0, 1)));
+        assert_eq!(&stamped, transaction.raster());
+        transaction.rollback();
+        assert_eq!(original, raster);
+        let mut transaction = raster.begin();
+        transaction.add_from(&sprite, (4, 0));
+        transaction.add_from(&sprite, (0, 1));
+        drop(transaction);
+        assert_eq!(original, raster);
+        let mut transaction = raster.begin();
+        transaction.add_from(&sprite, (4, 0));
+        transaction.add_from(&sprite, (0, 1));
+        transaction.commit();
+        assert_eq!(stamped, raster);
+    }
+
+    #[test]
+    fn test_rollback_random() {
+        let mut rng = rand::thread_rng();
+        let mut raster = BinaryRaster::from_fn(200, 40, |_, _| rng.gen_bool(0.1));
+        let original = raster.clone();
+        let mut transaction = raster.begin();
+        for _ in 0..20 {
+            let sprite = BinaryRaster::from_fn(rng.gen_range(1..80), rng.gen_range(1..10), |_, _| rng.gen_bool(0.5));
+            let pos = (rng.gen_range(-80..210), rng.gen_range(-10..45));
+            transaction.add_from(&sprite, pos);
+        }
+        transaction.rollback();
+        assert_eq!(original, raster);
+    }
+}
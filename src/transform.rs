@@ -0,0 +1,507 @@
+use crate::{bitline::{BitLine, EMPTY_LINE}, Affine2, BinaryRaster};
+const BLOCK_SIZE: usize = usize::BITS as usize;
+
+/// How a block of pixels is reduced to a single pixel when downscaling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownscaleMode {
+    /// The pixel is set if any pixel of the block is set
+    Any,
+    /// The pixel is set if more than half of the pixels of the block are set
+    Majority,
+    /// The pixel is set if every pixel of the block is set
+    All,
+}
+
+/// Transposes a square bit matrix in place, where bit j of block[i] is the element at row i and column j
+fn transpose_block(block: &mut [usize; BLOCK_SIZE]) {
+    let mut width = BLOCK_SIZE / 2;
+    let mut mask = usize::MAX >> width;
+    while width != 0 {
+        // swaps the top right and bottom left sub-blocks of every 2*width sized block on the diagonal
+        for start in (0..BLOCK_SIZE).step_by(2 * width) {
+            for i in start..(start + width) {
+                let swapped = ((block[i] >> width) ^ block[i + width]) & mask;
+                block[i] ^= swapped << width;
+                block[i + width] ^= swapped;
+            }
+        }
+        width /= 2;
+        mask ^= mask << width;
+    }
+}
+
+/// What happens to the content that a translation moves outside of the raster
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranslateMode {
+    /// The raster grows to cover both its original area and the translated content
+    Grow,
+    /// The raster keeps its size and the content moved outside of it is lost
+    Clip,
+}
+
+impl BinaryRaster {
+    /// Returns a copy of the raster with its content moved by (dx, dy)
+    /// With TranslateMode::Grow, the top left corner of the result is at (min(dx, 0), min(dy, 0)) relatively to self
+    pub fn translated(&self, dx: isize, dy: isize, mode: TranslateMode) -> BinaryRaster {
+        let (left, top) = (dx.max(0) as usize, dy.max(0) as usize);
+        let (right, bottom) = ((-dx).max(0) as usize, (-dy).max(0) as usize);
+        if mode == TranslateMode::Grow {
+            return self.padded(left, right, top, bottom);
+        }
+        let width = self.width();
+        BinaryRaster(
+            (0..self.height()).map(|y| {
+                match (y as isize - dy).try_into().ok().and_then(|src_y: usize| self.0.get(src_y)) {
+                    Some(bitline) if dx >= 0 => bitline.padded(left, 0).sub_line(0, width),
+                    Some(bitline) => bitline.sub_line(right, width),
+                    None => BitLine::new(width),
+                }
+            }).collect(),
+        )
+    }
+
+    /// Returns a copy of the raster with its content moved by (dx, dy) as if it was a torus,
+    /// the content going past one edge coming back from the opposite edge
+    pub fn shifted_wrapping(&self, dx: isize, dy: isize) -> BinaryRaster {
+        let mut lines: Vec<BitLine> = self.0.iter().map(|bitline| bitline.shifted_wrapping(dx)).collect();
+        if !lines.is_empty() {
+            let amount = dy.rem_euclid(lines.len() as isize) as usize;
+            lines.rotate_right(amount);
+        }
+        BinaryRaster(lines)
+    }
+
+    /// Builds a width x height raster by repeating tile, starting from the top left corner
+    pub fn tiled(tile: &BinaryRaster, width: usize, height: usize) -> BinaryRaster {
+        if tile.height() == 0 {
+            return BinaryRaster::new(width, height);
+        }
+        let rows: Vec<BitLine> = tile.0.iter().map(|bitline| bitline.repeated(width)).collect();
+        BinaryRaster(
+            rows.iter().cycle().take(height).cloned().collect(),
+        )
+    }
+
+    /// Splits the raster into tile_width x tile_height tiles along with the position of their top left corner, row by row
+    /// Tiles on the right and bottom edges are smaller if the raster size isn't a multiple of the tile size
+    pub fn split_tiles(&self, tile_width: usize, tile_height: usize) -> impl Iterator<Item = (usize, usize, BinaryRaster)> + '_ {
+        let (width, height) = if tile_width == 0 || tile_height == 0 { (0, 0) } else { (self.width(), self.height()) };
+        (0..height).step_by(tile_height.max(1))
+            .flat_map(move |y| (0..width).step_by(tile_width.max(1)).map(move |x| (x, y)))
+            .map(move |(x, y)| (x, y, self.crop(x, y, tile_width, tile_height)))
+    }
+
+    /// Returns a raster with other placed on the right of self
+    /// If their heights differ, the shorter one is padded with bits with a value of 0 at the bottom
+    pub fn concat_horizontal(&self, other: &BinaryRaster) -> BinaryRaster {
+        let (width, other_width) = (self.width(), other.width());
+        BinaryRaster(
+            (0..self.height().max(other.height())).map(|line_i| {
+                let mut bitline = self.0.get(line_i).unwrap_or(&EMPTY_LINE).padded(0, other_width);
+                bitline.bits = width + other_width;
+                bitline.combine_with(&other.0.get(line_i).unwrap_or(&EMPTY_LINE).padded(width, 0), |a, b| a | b);
+                bitline
+            }).collect(),
+        )
+    }
+
+    /// Returns a raster with other placed below self
+    /// If their widths differ, the narrower one is padded with bits with a value of 0 on the right
+    pub fn concat_vertical(&self, other: &BinaryRaster) -> BinaryRaster {
+        let width = self.width().max(other.width());
+        BinaryRaster(
+            self.0.iter().chain(other.0.iter())
+                .map(|bitline| bitline.padded(0, width - bitline.bits))
+                .collect(),
+        )
+    }
+
+    /// Returns a copy of the raster with its rows and columns swapped
+    /// The bits are moved one usize::BITS x usize::BITS block at a time
+    pub fn transposed(&self) -> BinaryRaster {
+        let (width, height) = (self.width(), self.height());
+        let mut res = BinaryRaster::new(height, width);
+        let mut block = [0; BLOCK_SIZE];
+        for block_y in 0..BitLine::chunks_to_fit(height) {
+            for chunk_i in 0..BitLine::chunks_to_fit(width) {
+                for (i, row) in block.iter_mut().enumerate() {
+                    *row = self.0.get(block_y * BLOCK_SIZE + i).map_or(0, |bitline| bitline.chunk(chunk_i));
+                }
+                transpose_block(&mut block);
+                for (bitline, &row) in res.0.iter_mut().skip(chunk_i * BLOCK_SIZE).zip(block.iter()) {
+                    bitline.set_chunk(block_y, row);
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns a copy of the raster mirrored left to right
+    pub fn flipped_horizontal(&self) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().map(|bitline| bitline.reversed()).collect(),
+        )
+    }
+
+    /// Returns a copy of the raster mirrored top to bottom
+    pub fn flipped_vertical(&self) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter().rev().cloned().collect(),
+        )
+    }
+
+    /// Returns the raster transformed by transform, along with the position of its top left corner in the transformed space
+    /// The result is sized to fit the whole transformed raster, each pixel taking the value of the nearest source pixel
+    /// A non invertible transform gives an empty raster
+    pub fn transformed(&self, transform: &Affine2) -> (BinaryRaster, (isize, isize)) {
+        let Some(inverse) = transform.inverse() else {
+            return (BinaryRaster(Vec::new()), (0, 0));
+        };
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        let corners = [(0., 0.), (width, 0.), (0., height), (width, height)].map(|(x, y)| transform.apply(x, y));
+        // the epsilon prevents rounding errors from adding an empty row or column
+        let min_x = (corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min) + 1e-9).floor();
+        let min_y = (corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min) + 1e-9).floor();
+        let max_x = (corners.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max) - 1e-9).ceil();
+        let max_y = (corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max) - 1e-9).ceil();
+        let res = BinaryRaster::from_fn((max_x - min_x).max(0.) as usize, (max_y - min_y).max(0.) as usize, |x, y| {
+            let (src_x, src_y) = inverse.apply(min_x + x as f64 + 0.5, min_y + y as f64 + 0.5);
+            src_x >= 0. && src_y >= 0. && self.get(src_x as usize, src_y as usize)
+        });
+        (res, (min_x as isize, min_y as isize))
+    }
+
+    /// Returns a copy of the raster where every pixel is replaced by a factor_x x factor_y block of that pixel
+    pub fn scaled_up(&self, factor_x: usize, factor_y: usize) -> BinaryRaster {
+        BinaryRaster(
+            self.0.iter()
+                .flat_map(|bitline| std::iter::repeat_n(bitline.scaled_up(factor_x), factor_y))
+                .collect(),
+        )
+    }
+
+    /// Returns a copy of the raster where every factor x factor block of pixels is reduced to a single pixel according to mode
+    /// Blocks on the right and bottom edges may be smaller, only the pixels inside the raster are considered
+    /// A factor of 0 gives an empty raster
+    pub fn scaled_down(&self, factor: usize, mode: DownscaleMode) -> BinaryRaster {
+        if factor == 0 {
+            return BinaryRaster(Vec::new());
+        }
+        let width = self.width();
+        let new_width = width.div_ceil(factor);
+        BinaryRaster(
+            self.0.chunks(factor).map(|lines| {
+                BitLine::from_fn(new_width, |x| {
+                    let (start, end) = (x * factor, ((x + 1) * factor).min(width));
+                    let count: usize = lines.iter().map(|bitline| bitline.count_ones_range(start, end)).sum();
+                    let block_size = (end - start) * lines.len();
+                    match mode {
+                        DownscaleMode::Any => count > 0,
+                        DownscaleMode::Majority => 2 * count > block_size,
+                        DownscaleMode::All => count == block_size,
+                    }
+                })
+            }).collect(),
+        )
+    }
+
+    /// Returns a copy of the raster rotated by 90° clockwise
+    pub fn rotated_90(&self) -> BinaryRaster {
+        self.transposed().flipped_horizontal()
+    }
+
+    /// Returns a copy of the raster rotated by 180°
+    pub fn rotated_180(&self) -> BinaryRaster {
+        self.flipped_horizontal().flipped_vertical()
+    }
+
+    /// Returns a copy of the raster rotated by 270° clockwise (90° counterclockwise)
+    pub fn rotated_270(&self) -> BinaryRaster {
+        self.transposed().flipped_vertical()
+    }
+
+    /// Returns a copy of the raster rotated clockwise by angle (in radians) around its center,
+    /// sized to fit the whole rotated raster, each pixel taking the value of the nearest source pixel
+    pub fn rotated(&self, angle: f64) -> BinaryRaster {
+        let ((new_width, new_height), sample) = self.rotation_sampler(angle);
+        BinaryRaster::from_fn(new_width, new_height, sample)
+    }
+
+    /// Adds source rotated clockwise by angle (in radians) to self, with the top left corner of its rotated bounds at pos,
+    /// same as add_from(&source.rotated(angle), pos) without allocating the rotated raster, clipped to the bounds of self
+    /// Returns the bounding box (x0, y0, x1, y1) of the bits that were added, None if none were
+    pub fn add_rotated_from(&mut self, source: &BinaryRaster, pos: (isize, isize), angle: f64) -> Option<(usize, usize, usize, usize)> {
+        let ((rotated_width, rotated_height), sample) = source.rotation_sampler(angle);
+        // the part of the rotated bounds that falls inside of self
+        let xs = (-pos.0).max(0)..(self.width() as isize - pos.0).min(rotated_width as isize);
+        let ys = (-pos.1).max(0)..(self.height() as isize - pos.1).min(rotated_height as isize);
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for y in ys {
+            for x in xs.clone().filter(|&x| sample(x as usize, y as usize)) {
+                let (x, y) = ((x + pos.0) as usize, (y + pos.1) as usize);
+                self.set(x, y, true);
+                bounds = Some(match bounds {
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                    None => (x, y, x, y),
+                });
+            }
+        }
+        bounds
+    }
+
+    /// The size of self rotated clockwise by angle (in radians),
+    /// and a function telling if the bit at (x, y) of the rotated raster has a value of 1
+    fn rotation_sampler(&self, angle: f64) -> ((usize, usize), impl Fn(usize, usize) -> bool + '_) {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        let (sin, cos) = angle.sin_cos();
+        // the epsilon prevents rounding errors from adding an empty row or column
+        let new_width = ((width * cos.abs() + height * sin.abs()) - 1e-9).ceil().max(0.) as usize;
+        let new_height = ((width * sin.abs() + height * cos.abs()) - 1e-9).ceil().max(0.) as usize;
+        let (new_cx, new_cy) = (new_width as f64 / 2., new_height as f64 / 2.);
+        let sample = move |x: usize, y: usize| {
+            let (dx, dy) = (x as f64 + 0.5 - new_cx, y as f64 + 0.5 - new_cy);
+            let src_x = dx * cos + dy * sin + width / 2.;
+            let src_y = -dx * sin + dy * cos + height / 2.;
+            src_x >= 0. && src_y >= 0. && self.get(src_x as usize, src_y as usize)
+        };
+        ((new_width, new_height), sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+    use crate::{Affine2, BinaryRaster, DownscaleMode, TranslateMode};
+
+    fn raster() -> BinaryRaster {
+        "
+            ###
+            #..
+        ".parse().unwrap()
+    }
+
+    #[test]
+    fn test_add_rotated_from() {
+        let mut rng = rand::thread_rng();
+        let sprite = BinaryRaster::from_fn(13, 7, |_, _| rng.gen_bool(0.5));
+        for angle in [0., 0.3, 1., std::f64::consts::FRAC_PI_2, 2.5] {
+            for pos in [(5, 4), (-6, -3), (25, -2)] {
+                let mut truth = BinaryRaster::new(30, 30);
+                truth.add_from(&sprite.rotated(angle), pos);
+                let mut raster = BinaryRaster::new(30, 30);
+                let bounds = raster.add_rotated_from(&sprite, pos, angle);
+                assert_eq!(truth, raster);
+                assert_eq!(truth.bounding_box(), bounds);
+            }
+        }
+        // clipped to the bounds of self
+        let mut canvas = BinaryRaster::new(3, 2);
+        assert_eq!(Some((1, 1, 2, 1)), canvas.add_rotated_from(&raster(), (1, 1), 0.));
+        assert_eq!(BinaryRaster::from_rows(&[&[0, 0, 0], &[0, 1, 1]]).unwrap(), canvas);
+        assert_eq!(None, canvas.add_rotated_from(&raster(), (3, 0), 0.));
+    }
+
+    #[test]
+    fn test_flips() {
+        let flipped_horizontal: BinaryRaster = "
+            ###
+            ..#
+        ".parse().unwrap();
+        let flipped_vertical: BinaryRaster = "
+            #..
+            ###
+        ".parse().unwrap();
+        assert_eq!(flipped_horizontal, raster().flipped_horizontal());
+        assert_eq!(flipped_vertical, raster().flipped_vertical());
+        assert_eq!(raster().rotated_180(), raster().rotated_90().rotated_90());
+    }
+
+    #[test]
+    fn test_transposed() {
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(150, 70, |_, _| rng.gen_bool(0.3));
+        let transposed = raster.transposed();
+        assert_eq!((70, 150), (transposed.width(), transposed.height()));
+        assert_eq!(BinaryRaster::from_fn(70, 150, |x, y| raster.get(y, x)), transposed);
+        assert_eq!(raster, transposed.transposed());
+    }
+
+    #[test]
+    fn test_rotated() {
+        use std::f64::consts::PI;
+        let mut rng = rand::thread_rng();
+        let raster = BinaryRaster::from_fn(7, 4, |_, _| rng.gen_bool(0.5));
+        assert_eq!(raster, raster.rotated(0.));
+        assert_eq!(raster.rotated_90(), raster.rotated(PI / 2.));
+        assert_eq!(raster.rotated_180(), raster.rotated(PI));
+        let octagon: BinaryRaster = "
+            .##.
+            ####
+            ####
+            .##.
+        ".parse().unwrap();
+        let square = BinaryRaster::from_raster(&[1; 16], 4);
+        let rotated = square.rotated(PI / 4.);
+        assert_eq!((6, 6), (rotated.width(), rotated.height()));
+        assert_eq!((octagon, (1, 1)), rotated.crop_to_content());
+    }
+
+    #[test]
+    fn test_scaled_up() {
+        let scaled: BinaryRaster = "
+            ######
+            ######
+            ##....
+            ##....
+        ".parse().unwrap();
+        assert_eq!(scaled, raster().scaled_up(2, 2));
+    }
+
+    #[test]
+    fn test_scaled_down() {
+        let mask: BinaryRaster = "
+            #..##
+            .#.##
+            ....#
+        ".parse().unwrap();
+        let any: BinaryRaster = "
+            ###
+            ..#
+        ".parse().unwrap();
+        let majority: BinaryRaster = "
+            ..#
+            ..#
+        ".parse().unwrap();
+        let all: BinaryRaster = "
+            ..#
+            ..#
+        ".parse().unwrap();
+        assert_eq!(any, mask.scaled_down(2, DownscaleMode::Any));
+        assert_eq!(majority, mask.scaled_down(2, DownscaleMode::Majority));
+        assert_eq!(all, mask.scaled_down(2, DownscaleMode::All));
+        assert_eq!(raster(), raster().scaled_up(3, 3).scaled_down(3, DownscaleMode::All));
+    }
+
+    #[test]
+    fn test_transformed() {
+        let (scaled, offset) = raster().transformed(&Affine2::scale(2., 2.).then(&Affine2::translation(-3., 1.)));
+        assert_eq!((raster().scaled_up(2, 2), (-3, 1)), (scaled, offset));
+        let (rotated, offset) = raster().transformed(&Affine2::rotation(std::f64::consts::PI / 2.));
+        assert_eq!((raster().rotated_90(), (-2, 0)), (rotated, offset));
+        let sheared: BinaryRaster = "
+            ###.
+            .#..
+        ".parse().unwrap();
+        assert_eq!((sheared, (0, 0)), raster().transformed(&Affine2::shear(0.5, 0.)));
+    }
+
+    #[test]
+    fn test_translated() {
+        let grown: BinaryRaster = "
+            ....
+            .###
+            .#..
+        ".parse().unwrap();
+        assert_eq!(grown, raster().translated(1, 1, TranslateMode::Grow));
+        let grown: BinaryRaster = "
+            ###.
+            #...
+            ....
+        ".parse().unwrap();
+        assert_eq!(grown, raster().translated(-1, -1, TranslateMode::Grow));
+        let clipped: BinaryRaster = "
+            ...
+            .##
+        ".parse().unwrap();
+        assert_eq!(clipped, raster().translated(1, 1, TranslateMode::Clip));
+        let clipped: BinaryRaster = "
+            ##.
+            ...
+        ".parse().unwrap();
+        assert_eq!(clipped, raster().translated(-1, 0, TranslateMode::Clip));
+        assert_eq!(BinaryRaster::new(3, 2), raster().translated(0, -5, TranslateMode::Clip));
+        let mut rng = rand::thread_rng();
+        let wide = BinaryRaster::from_fn(300, 3, |_, _| rng.gen_bool(0.5));
+        let moved = wide.translated(-130, 1, TranslateMode::Clip);
+        assert_eq!(BinaryRaster::from_fn(300, 3, |x, y| y > 0 && wide.get(x + 130, y - 1)), moved);
+    }
+
+    #[test]
+    fn test_shifted_wrapping() {
+        let shifted: BinaryRaster = "
+            .#.
+            ###
+        ".parse().unwrap();
+        assert_eq!(shifted, raster().shifted_wrapping(1, 1));
+        assert_eq!(shifted, raster().shifted_wrapping(-2, -3));
+    }
+
+    #[test]
+    fn test_tiled() {
+        let tiled: BinaryRaster = "
+            #####
+            #..#.
+            #####
+        ".parse().unwrap();
+        assert_eq!(tiled, BinaryRaster::tiled(&raster(), 5, 3));
+        assert_eq!(BinaryRaster::new(2, 2), BinaryRaster::tiled(&BinaryRaster::new(0, 0), 2, 2));
+    }
+
+    #[test]
+    fn test_split_tiles() {
+        let tiles = raster().split_tiles(2, 1).collect::<Vec<_>>();
+        let expected = [
+            (0, 0, "##"),
+            (2, 0, "#"),
+            (0, 1, "#."),
+            (2, 1, "."),
+        ].map(|(x, y, tile)| (x, y, tile.parse::<BinaryRaster>().unwrap()));
+        assert_eq!(expected.to_vec(), tiles);
+        assert_eq!(0, raster().split_tiles(0, 1).count());
+    }
+
+    #[test]
+    fn test_concat() {
+        let other: BinaryRaster = "
+            ##
+            .#
+            #.
+        ".parse().unwrap();
+        let horizontal: BinaryRaster = "
+            #####
+            #...#
+            ...#.
+        ".parse().unwrap();
+        let vertical: BinaryRaster = "
+            ###
+            #..
+            ##.
+            .#.
+            #..
+        ".parse().unwrap();
+        assert_eq!(horizontal, raster().concat_horizontal(&other));
+        assert_eq!(vertical, raster().concat_vertical(&other));
+    }
+
+    #[test]
+    fn test_rotations() {
+        let rotated_90: BinaryRaster = "
+            ##
+            .#
+            .#
+        ".parse().unwrap();
+        let rotated_180: BinaryRaster = "
+            ..#
+            ###
+        ".parse().unwrap();
+        let rotated_270: BinaryRaster = "
+            #.
+            #.
+            ##
+        ".parse().unwrap();
+        assert_eq!(rotated_90, raster().rotated_90());
+        assert_eq!(rotated_180, raster().rotated_180());
+        assert_eq!(rotated_270, raster().rotated_270());
+        assert_eq!(raster(), raster().rotated_90().rotated_270());
+    }
+}